@@ -0,0 +1,168 @@
+use std::cell::Cell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use chrono::Local;
+use crate::history::{history_file, HistoryEntry};
+use crate::storage::{open_storage, write_atomically, Storage, StorageKind};
+use crate::task::Task;
+
+/// Rotating timestamped copies of a data file, kept next to it as
+/// `<file>.<timestamp>.bak`. A CSV or JSON file's history is copied along
+/// with it, so a restore brings back the history that goes with the tasks.
+pub struct Backups {
+    data_file: PathBuf,
+    keep: usize,
+}
+
+impl Backups {
+    pub fn new(data_file: &str, keep: usize) -> Backups {
+        Backups {
+            data_file: PathBuf::from(data_file),
+            keep,
+        }
+    }
+
+    fn directory(&self) -> PathBuf {
+        match self.data_file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    }
+
+    fn prefix(&self) -> String {
+        let file_name = self.data_file.file_name().map(|name| name.to_string_lossy().to_string());
+        format!("{}.", file_name.unwrap_or_default())
+    }
+
+    /// Copies the current data file to a new backup and removes the oldest
+    /// backups beyond the configured number. Does nothing if there is no file yet.
+    pub fn create(&self) -> io::Result<Option<PathBuf>> {
+        if self.keep == 0 || !self.data_file.exists() {
+            return Ok(None);
+        }
+
+        let timestamp = Local::now().format("%Y%m%d-%H%M%S-%3f");
+        let backup = self.directory().join(format!("{}{}.bak", self.prefix(), timestamp));
+        fs::copy(&self.data_file, &backup)?;
+        let history = history_file(&self.data_file);
+        if history.exists() {
+            fs::copy(&history, history_file(&backup))?;
+        }
+
+        for old in self.list()?.into_iter().skip(self.keep) {
+            fs::remove_file(&old)?;
+            let old_history = history_file(&old);
+            if old_history.exists() {
+                fs::remove_file(old_history)?;
+            }
+        }
+        Ok(Some(backup))
+    }
+
+    /// Returns all backups, newest first.
+    pub fn list(&self) -> io::Result<Vec<PathBuf>> {
+        let prefix = self.prefix();
+        let directory = self.directory();
+        if !directory.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups: Vec<PathBuf> = fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".bak"))
+            })
+            .collect();
+        // Timestamps sort lexically
+        backups.sort();
+        backups.reverse();
+        Ok(backups)
+    }
+
+    /// Replaces the data file and its history with the given backup. The
+    /// current files are backed up first, so a restore can itself be undone.
+    pub fn restore(&self, backup: &Path) -> io::Result<()> {
+        let content = fs::read(backup)?;
+        let backup_history = history_file(backup);
+        let history = if backup_history.exists() { Some(fs::read(backup_history)?) } else { None };
+        self.create()?;
+        write_atomically(&self.data_file, |file| io::Write::write_all(file, &content))?;
+        let data_history = history_file(&self.data_file);
+        match history {
+            Some(history) => write_atomically(&data_history, |file| io::Write::write_all(file, &history)),
+            // The tasks had no history yet when the backup was made
+            None if data_history.exists() => fs::remove_file(data_history),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Wraps a storage backend so the data file is backed up once per run,
+/// right before the first write.
+pub struct BackedUpStorage {
+    inner: Box<dyn Storage>,
+    backups: Backups,
+    backed_up: Cell<bool>,
+}
+
+impl BackedUpStorage {
+    pub fn new(inner: Box<dyn Storage>, backups: Backups) -> BackedUpStorage {
+        BackedUpStorage {
+            inner,
+            backups,
+            backed_up: Cell::new(false),
+        }
+    }
+
+    /// Opens a backend for a data file with backups of it.
+    pub fn open(kind: StorageKind, file_path: String, keep: usize) -> io::Result<BackedUpStorage> {
+        let backups = Backups::new(&file_path, keep);
+        Ok(BackedUpStorage::new(open_storage(kind, file_path)?, backups))
+    }
+
+    fn backup_once(&self) -> io::Result<()> {
+        if !self.backed_up.get() {
+            self.backups.create()?;
+            self.backed_up.set(true);
+        }
+        Ok(())
+    }
+}
+
+impl Storage for BackedUpStorage {
+    fn load_tasks(&self) -> io::Result<Vec<Task>> {
+        self.inner.load_tasks()
+    }
+
+    fn save_tasks(&self, tasks: &[&Task]) -> io::Result<()> {
+        self.backup_once()?;
+        self.inner.save_tasks(tasks)
+    }
+
+    fn upsert_task(&self, task: &Task) -> io::Result<()> {
+        self.backup_once()?;
+        self.inner.upsert_task(task)
+    }
+
+    fn delete_task(&self, id: u32) -> io::Result<()> {
+        self.backup_once()?;
+        self.inner.delete_task(id)
+    }
+
+    fn updates_incrementally(&self) -> bool {
+        self.inner.updates_incrementally()
+    }
+
+    fn load_history(&self) -> io::Result<Vec<HistoryEntry>> {
+        self.inner.load_history()
+    }
+
+    // Appending can't lose anything, so it doesn't need a backup
+    fn append_history(&self, entries: &[HistoryEntry]) -> io::Result<()> {
+        self.inner.append_history(entries)
+    }
+}
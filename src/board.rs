@@ -0,0 +1,208 @@
+use colored::Colorize;
+use crate::console::{clear_console, read_input, task_actions, wait};
+use crate::status::Status;
+use crate::task::Task;
+use crate::task_manager::TaskManager;
+
+const COLUMN_WIDTH: usize = 24;
+
+/// Priority bands used as board columns: name, lowest and highest priority,
+/// and the priority a card gets when moved into the band.
+const PRIORITY_BANDS: [(&str, u8, u8, u8); 3] = [
+    ("High", 7, 10, 8),
+    ("Medium", 4, 6, 5),
+    ("Low", 0, 3, 2),
+];
+
+/// How tasks are split into columns.
+pub enum Grouping {
+    Status,
+    Priority,
+    /// One column per tag in this order, plus one for tasks with none of them
+    Tags(Vec<String>),
+}
+
+pub struct Column<'a> {
+    pub title: String,
+    pub tasks: Vec<&'a Task>,
+}
+
+/// Lays tasks out in columns. Status boards include closed tasks, the others
+/// only pending ones. Archived tasks are left out.
+pub fn columns<'a>(task_manager: &'a TaskManager, grouping: &Grouping) -> Vec<Column<'a>> {
+    let tasks: Vec<&Task> = task_manager.get_all_tasks().into_iter().filter(|task| !task.is_archived()).collect();
+    let pending: Vec<&Task> = tasks.iter().copied().filter(|task| task.is_pending()).collect();
+    match grouping {
+        Grouping::Status => Status::ALL.iter()
+            .map(|status| Column {
+                title: status.label().to_string(),
+                tasks: tasks.iter().copied().filter(|task| task.status() == *status).collect(),
+            })
+            .collect(),
+        Grouping::Priority => PRIORITY_BANDS.iter()
+            .map(|(name, low, high, _)| Column {
+                title: name.to_string(),
+                tasks: pending.iter().copied().filter(|task| (*low..=*high).contains(&task.priority())).collect(),
+            })
+            .collect(),
+        Grouping::Tags(tags) => {
+            // A task with several of the tags goes in the first matching column
+            let column_of = |task: &Task| tags.iter().position(|tag| task.tags().contains(tag)).unwrap_or(tags.len());
+            tags.iter()
+                .map(|tag| tag.to_string())
+                .chain(std::iter::once("(none)".to_string()))
+                .enumerate()
+                .map(|(index, title)| Column {
+                    title,
+                    tasks: pending.iter().copied().filter(|task| column_of(task) == index).collect(),
+                })
+                .collect()
+        }
+    }
+}
+
+/// Moves a task to a column by updating the field the board is grouped by.
+pub fn move_card(task_manager: &mut TaskManager, grouping: &Grouping, id: u32, column: usize) -> Result<(), String> {
+    let task = task_manager.get_task(id).ok_or("Task not found.")?;
+    match grouping {
+        Grouping::Status => {
+            let status = *Status::ALL.get(column).ok_or("No such column.")?;
+            task_manager.set_status(id, status).map(|_| ())
+        }
+        Grouping::Priority => {
+            let (_, low, high, priority) = *PRIORITY_BANDS.get(column).ok_or("No such column.")?;
+            if (low..=high).contains(&task.priority()) {
+                return Ok(());
+            }
+            let mut task = task.clone();
+            task.set_priority(priority)?;
+            task_manager.update_task(task);
+            Ok(())
+        }
+        Grouping::Tags(tags) => {
+            if column > tags.len() {
+                return Err("No such column.".to_string());
+            }
+            let mut task = task.clone();
+            for tag in tags {
+                task.remove_tag(tag);
+            }
+            if let Some(tag) = tags.get(column) {
+                task.add_tag(tag.clone());
+            }
+            task_manager.update_task(task);
+            Ok(())
+        }
+    }
+}
+
+/// Finds a column by number (starting at 1) or title.
+pub fn find_column(columns: &[Column], reference: &str) -> Option<usize> {
+    match reference.parse::<usize>() {
+        Ok(number) if number > 0 && number <= columns.len() => Some(number - 1),
+        _ => columns.iter().position(|column| column.title.eq_ignore_ascii_case(reference.trim())),
+    }
+}
+
+/// Prints the columns side by side with every card numbered, and returns the
+/// task IDs in that order.
+pub fn print_board(task_manager: &TaskManager, columns: &[Column]) -> Vec<u32> {
+    let header: Vec<String> = columns.iter().enumerate()
+        .map(|(i, column)| fit(&format!("{}. {} ({})", i + 1, column.title, column.tasks.len())))
+        .collect();
+    println!("{}", header.iter().map(|title| title.bold().to_string()).collect::<Vec<String>>().join(" │ "));
+    println!("{}", vec!["─".repeat(COLUMN_WIDTH); columns.len()].join("─┼─"));
+
+    // Cards are numbered column by column so the numbers read top to bottom
+    let mut ids = Vec::new();
+    let mut numbers = Vec::new();
+    for column in columns {
+        numbers.push(ids.len());
+        ids.extend(column.tasks.iter().map(|task| task.id()));
+    }
+    let rows = columns.iter().map(|column| column.tasks.len()).max().unwrap_or(0);
+    for row in 0..rows {
+        let cells: Vec<String> = columns.iter().zip(&numbers)
+            .map(|(column, first)| match column.tasks.get(row) {
+                Some(task) => card(task_manager, first + row + 1, task),
+                None => " ".repeat(COLUMN_WIDTH),
+            })
+            .collect();
+        println!("{}", cells.join(" │ "));
+    }
+    ids
+}
+
+fn card(task_manager: &TaskManager, number: usize, task: &Task) -> String {
+    let text = fit(&format!("{}. {}", number, task.name()));
+    if task.is_overdue() {
+        text.red().to_string()
+    } else if !task.is_pending() || task_manager.is_blocked(task) {
+        text.dimmed().to_string()
+    } else {
+        text
+    }
+}
+
+/// Pads or cuts text to the column width.
+fn fit(text: &str) -> String {
+    if text.chars().count() > COLUMN_WIDTH {
+        format!("{}…", text.chars().take(COLUMN_WIDTH - 1).collect::<String>())
+    } else {
+        format!("{:<width$}", text, width = COLUMN_WIDTH)
+    }
+}
+
+pub fn display_board(task_manager: &mut TaskManager) {
+    let grouping = match read_input("Group by (S)tatus, (P)riority or (T)ags:").to_uppercase().as_str() {
+        "S" | "" => Grouping::Status,
+        "P" => Grouping::Priority,
+        "T" => {
+            let tags: Vec<String> = read_input("Enter the tags to use as columns, in order (comma-separated):")
+                .split(',')
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+            if tags.is_empty() {
+                println!("No tags given.");
+                wait();
+                return;
+            }
+            Grouping::Tags(tags)
+        }
+        _ => {
+            println!("Invalid option.");
+            wait();
+            return;
+        }
+    };
+
+    loop {
+        clear_console();
+        let board = columns(task_manager, &grouping);
+        let ids = print_board(task_manager, &board);
+        let choice = read_input("\nMove a card with '<card> <column>' (e.g. '2 3' or '2 done'), enter a card number to open it, or nothing to go back");
+        let parts: Vec<&str> = choice.splitn(2, ' ').collect();
+        let card = match parts[0].parse::<usize>() {
+            Ok(number) if number > 0 && number <= ids.len() => ids[number - 1],
+            _ if choice.is_empty() => return,
+            _ => {
+                println!("Invalid card number.");
+                wait();
+                continue;
+            }
+        };
+        match parts.get(1) {
+            Some(column) => {
+                let result = find_column(&board, column)
+                    .ok_or_else(|| format!("No column '{}'.", column.trim()))
+                    .and_then(|column| move_card(task_manager, &grouping, card, column));
+                if let Err(e) = result {
+                    println!("Error: {}", e);
+                    wait();
+                }
+            }
+            None => task_actions(task_manager, card),
+        }
+    }
+}
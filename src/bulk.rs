@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use crate::console::{clear_console, print_task_line, read_input, wait};
+use crate::query::Query;
+use crate::status::Status;
+use crate::task::Task;
+use crate::task_manager::TaskManager;
+
+/// Something to do to every selected task.
+pub enum BulkAction {
+    Complete,
+    Delete,
+    AddTag(String),
+    RemoveTag(String),
+    SetPriority(u8),
+    /// Move due dates by this many days (back if negative)
+    ShiftDue(i64),
+}
+
+impl BulkAction {
+    fn describe(&self, count: usize) -> String {
+        match self {
+            BulkAction::Complete => format!("Complete {} task(s)", count),
+            BulkAction::Delete => format!("Delete {} task(s)", count),
+            BulkAction::AddTag(tag) => format!("Tag {} task(s) with '{}'", count, tag),
+            BulkAction::RemoveTag(tag) => format!("Remove tag '{}' from {} task(s)", tag, count),
+            BulkAction::SetPriority(priority) => format!("Set priority {} on {} task(s)", priority, count),
+            BulkAction::ShiftDue(days) => format!("Shift due dates of {} task(s) by {} day(s)", count, days),
+        }
+    }
+}
+
+/// What a bulk action did, by task name.
+#[derive(Default)]
+pub struct BulkSummary {
+    pub description: String,
+    pub changed: Vec<String>,
+    /// Tasks left alone, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Applies an action to each task as one change, so a single undo reverts it.
+pub fn apply(task_manager: &mut TaskManager, ids: &[u32], action: &BulkAction) -> BulkSummary {
+    let description = action.describe(ids.len());
+    task_manager.record(description.clone(), |task_manager| {
+        let mut summary = BulkSummary { description, ..Default::default() };
+        // Names up front, since deleted subtasks are gone by the time they come up
+        let names: HashMap<u32, String> = ids.iter()
+            .filter_map(|id| task_manager.get_task(*id))
+            .map(|task| (task.id(), task.name().to_string()))
+            .collect();
+        // Subtasks closed or deleted along with a parent earlier in the selection
+        let mut done_with_parent: Vec<u32> = Vec::new();
+        for &id in ids {
+            if done_with_parent.contains(&id) {
+                summary.changed.extend(names.get(&id).cloned());
+                continue;
+            }
+            let Some(task) = task_manager.get_task(id).cloned() else {
+                continue;
+            };
+            let name = task.name().to_string();
+            let result = match action {
+                BulkAction::Complete if task.status() == Status::Done => Err("already done".to_string()),
+                BulkAction::Complete => task_manager.set_status(id, Status::Done).map(|change| {
+                    done_with_parent.extend(change.closed_subtasks);
+                }),
+                BulkAction::Delete => {
+                    done_with_parent.extend(task_manager.descendants_of(id));
+                    task_manager.remove_task(id);
+                    Ok(())
+                }
+                _ => edit(task, action).map(|task| {
+                    task_manager.update_task(task);
+                }),
+            };
+            match result {
+                Ok(()) => summary.changed.push(name),
+                Err(reason) => summary.skipped.push((name, reason.trim_end_matches('.').to_lowercase())),
+            }
+        }
+        summary
+    })
+}
+
+// Applies one of the actions that edit a task's fields
+fn edit(mut task: Task, action: &BulkAction) -> Result<Task, String> {
+    match action {
+        BulkAction::AddTag(tag) if task.tags().contains(tag) => return Err("already tagged".to_string()),
+        BulkAction::AddTag(tag) => task.add_tag(tag.clone()),
+        BulkAction::RemoveTag(tag) if !task.tags().contains(tag) => return Err("not tagged".to_string()),
+        BulkAction::RemoveTag(tag) => task.remove_tag(tag),
+        BulkAction::SetPriority(priority) if task.priority() == *priority => return Err("already has this priority".to_string()),
+        BulkAction::SetPriority(priority) => task.set_priority(*priority)?,
+        BulkAction::ShiftDue(days) => task.shift_due(*days)?,
+        BulkAction::Complete | BulkAction::Delete => unreachable!("not a field edit"),
+    }
+    Ok(task)
+}
+
+/// Parses a selection like `1-4,7` into numbers in the order given, each
+/// between 1 and `max`.
+pub fn parse_selection(input: &str, max: usize) -> Result<Vec<usize>, String> {
+    let mut numbers: Vec<usize> = Vec::new();
+    for part in input.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+        let number = |text: &str| text.trim().parse::<usize>().map_err(|_| format!("'{}' is not a number.", text.trim()));
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (number(first)?, number(last)?),
+            None => (number(part)?, number(part)?),
+        };
+        if first > last {
+            return Err(format!("'{}' is backwards; write it as {}-{}.", part, last, first));
+        }
+        if first == 0 || last > max {
+            return Err(format!("'{}' is out of range; choose from 1 to {}.", part, max));
+        }
+        for number in first..=last {
+            if !numbers.contains(&number) {
+                numbers.push(number);
+            }
+        }
+    }
+    if numbers.is_empty() {
+        return Err("Nothing selected.".to_string());
+    }
+    Ok(numbers)
+}
+
+/// Whether input looks like a selection of numbers rather than a query.
+pub fn is_selection(input: &str) -> bool {
+    input.chars().all(|c| c.is_ascii_digit() || c == ',' || c == '-' || c == ' ')
+}
+
+pub fn print_summary(summary: &BulkSummary) {
+    println!("{}: {} changed, {} skipped.", summary.description, summary.changed.len(), summary.skipped.len());
+    if !summary.changed.is_empty() {
+        println!("Changed: {}", summary.changed.join(", "));
+    }
+    for (name, reason) in &summary.skipped {
+        println!("Skipped '{}': {}", name, reason);
+    }
+}
+
+/// Selects tasks by their numbers in `listed` or by a query, then applies an
+/// action to all of them after confirmation.
+pub fn display_bulk(task_manager: &mut TaskManager, listed: &[u32]) {
+    let input = read_input("Select tasks by number (e.g. 1-4,7) or with a query (e.g. tag:work and due<today):");
+    let ids: Vec<u32> = if input.is_empty() {
+        return;
+    } else if is_selection(&input) {
+        match parse_selection(&input, listed.len()) {
+            Ok(numbers) => numbers.iter().map(|number| listed[number - 1]).collect(),
+            Err(e) => {
+                println!("{}", e);
+                wait();
+                return;
+            }
+        }
+    } else {
+        match Query::parse(&input) {
+            Ok(query) => query.run(task_manager).iter().map(|task| task.id()).collect(),
+            Err(e) => {
+                println!("{}", e.explain(&input));
+                wait();
+                return;
+            }
+        }
+    };
+    if ids.is_empty() {
+        println!("No tasks selected.");
+        wait();
+        return;
+    }
+
+    clear_console();
+    println!("Selected tasks:");
+    for (i, id) in ids.iter().enumerate() {
+        print_task_line(task_manager, i + 1, 0, task_manager.get_task(*id).unwrap());
+    }
+    let action = match read_input("\n[C]omplete, [D]elete, [A]dd tag, [R]emove tag, Set [P]riority or [S]hift due dates:").to_uppercase().as_str() {
+        "C" => BulkAction::Complete,
+        "D" => BulkAction::Delete,
+        "A" => BulkAction::AddTag(read_input("Enter tag to add:")),
+        "R" => BulkAction::RemoveTag(read_input("Enter tag to remove:")),
+        "P" => match read_input("Enter priority (0-10):").parse::<u8>() {
+            Ok(priority) if priority <= 10 => BulkAction::SetPriority(priority),
+            _ => {
+                println!("Priority must be a number from 0 to 10.");
+                wait();
+                return;
+            }
+        },
+        "S" => match read_input("Shift due dates by how many days (negative to move them earlier)?").parse::<i64>() {
+            Ok(days) => BulkAction::ShiftDue(days),
+            Err(_) => {
+                println!("Enter a whole number of days.");
+                wait();
+                return;
+            }
+        },
+        _ => return,
+    };
+    if matches!(&action, BulkAction::AddTag(tag) | BulkAction::RemoveTag(tag) if tag.is_empty()) {
+        println!("No tag given.");
+        wait();
+        return;
+    }
+
+    let confirm = read_input(&format!("{}? (y/n)", action.describe(ids.len())));
+    if confirm.to_lowercase() != "y" {
+        println!("Nothing changed.");
+        wait();
+        return;
+    }
+    let summary = apply(task_manager, &ids, &action);
+    print_summary(&summary);
+    if !summary.changed.is_empty() {
+        println!("Undo from the main menu with 'U'.");
+    }
+    wait();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selections_keep_the_order_given() {
+        assert_eq!(parse_selection("1-3,5", 5), Ok(vec![1, 2, 3, 5]));
+        assert_eq!(parse_selection(" 4 , 2-3, 3 ,", 5), Ok(vec![4, 2, 3]));
+        assert_eq!(parse_selection("2-2", 2), Ok(vec![2]));
+    }
+
+    #[test]
+    fn selection_errors() {
+        assert_eq!(parse_selection("3-1", 5), Err("'3-1' is backwards; write it as 1-3.".to_string()));
+        assert_eq!(parse_selection("0", 5), Err("'0' is out of range; choose from 1 to 5.".to_string()));
+        assert_eq!(parse_selection("4-6", 5), Err("'4-6' is out of range; choose from 1 to 5.".to_string()));
+        assert_eq!(parse_selection("1,x", 5), Err("'x' is not a number.".to_string()));
+        assert_eq!(parse_selection("1-", 5), Err("'' is not a number.".to_string()));
+        assert_eq!(parse_selection("99999999999999999999999", 5), Err("'99999999999999999999999' is not a number.".to_string()));
+        assert_eq!(parse_selection(" , ", 5), Err("Nothing selected.".to_string()));
+        assert_eq!(parse_selection("1", 0), Err("'1' is out of range; choose from 1 to 0.".to_string()));
+    }
+
+    #[test]
+    fn selections_are_told_apart_from_queries() {
+        assert!(is_selection("1-3, 5"));
+        assert!(!is_selection("tag:work"));
+        assert!(!is_selection("priority>5"));
+    }
+}
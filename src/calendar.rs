@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+use colored::Colorize;
+use crate::console::{clear_console, print_task_line, read_input, read_task_details, task_actions, wait};
+use crate::task::Task;
+use crate::task_manager::TaskManager;
+
+pub struct Agenda<'a> {
+    /// Tasks past their due date, from any day
+    pub overdue: Vec<&'a Task>,
+    /// Each day of the agenda with the tasks due on it that aren't overdue yet
+    pub days: Vec<(NaiveDate, Vec<&'a Task>)>,
+}
+
+/// One day of a month calendar.
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub due: usize,
+    pub overdue: bool,
+}
+
+/// Pending tasks with a due date, grouped by the (local) day they are due on.
+pub fn tasks_by_day(task_manager: &TaskManager) -> BTreeMap<NaiveDate, Vec<&Task>> {
+    let mut by_day: BTreeMap<NaiveDate, Vec<&Task>> = BTreeMap::new();
+    for task in task_manager.get_all_pending_tasks_with_due_date() {
+        by_day.entry(task.due_date().unwrap()).or_default().push(task);
+    }
+    by_day
+}
+
+/// Overdue tasks and the tasks due on each of the `days` days starting today.
+pub fn agenda(task_manager: &TaskManager, days: u64) -> Agenda<'_> {
+    let today = Local::now().date_naive();
+    let by_day = tasks_by_day(task_manager);
+    let overdue = by_day.values().flatten().copied().filter(|task| task.is_overdue()).collect();
+    // Days past the last representable date are left out
+    let days = (0..days)
+        .map_while(|offset| today.checked_add_days(Days::new(offset)))
+        .map(|date| {
+            let tasks = by_day.get(&date).into_iter().flatten().copied().filter(|task| !task.is_overdue()).collect();
+            (date, tasks)
+        })
+        .collect();
+    Agenda { overdue, days }
+}
+
+/// Every day of the month containing `month`, with its number of tasks due.
+pub fn month(task_manager: &TaskManager, month: NaiveDate) -> Vec<CalendarDay> {
+    let by_day = tasks_by_day(task_manager);
+    let first = month.with_day(1).unwrap();
+    let next_month = first.checked_add_months(Months::new(1));
+    first.iter_days()
+        .take_while(|date| next_month.is_none_or(|next_month| *date < next_month))
+        .map(|date| {
+            let tasks = by_day.get(&date).map(|tasks| tasks.as_slice()).unwrap_or(&[]);
+            CalendarDay {
+                date,
+                due: tasks.len(),
+                overdue: tasks.iter().any(|task| task.is_overdue()),
+            }
+        })
+        .collect()
+}
+
+/// Prints the agenda with every task numbered, and returns the task IDs in
+/// that order.
+pub fn print_agenda(task_manager: &TaskManager, days: u64) -> Vec<u32> {
+    let agenda = agenda(task_manager, days);
+    let today = Local::now().date_naive();
+    let mut ids = Vec::new();
+
+    if !agenda.overdue.is_empty() {
+        println!("{}", "Overdue".red().bold());
+        for task in &agenda.overdue {
+            ids.push(task.id());
+            print_task_line(task_manager, ids.len(), 0, task);
+        }
+        println!();
+    }
+    for (date, tasks) in &agenda.days {
+        let label = match (*date - today).num_days() {
+            0 => " (today)",
+            1 => " (tomorrow)",
+            _ => "",
+        };
+        println!("{}{}", date.format("%a %Y-%m-%d").to_string().bold(), label);
+        if tasks.is_empty() {
+            println!("   {}", "Nothing due".dimmed());
+        }
+        for task in tasks {
+            ids.push(task.id());
+            print_task_line(task_manager, ids.len(), 0, task);
+        }
+    }
+    ids
+}
+
+/// Prints a month as a grid with the number of tasks due on each day.
+/// Overdue days are red and today is underlined.
+pub fn print_month(task_manager: &TaskManager, month_date: NaiveDate) {
+    let today = Local::now().date_naive();
+    let days = month(task_manager, month_date);
+
+    println!("{:^49}", month_date.format("%B %Y").to_string().bold());
+    println!("{}", ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"].iter().map(|day| format!("{:>3}    ", day)).collect::<String>());
+    // Leading blanks up to the weekday of the 1st
+    let mut line = "       ".repeat(days[0].date.weekday().num_days_from_monday() as usize);
+    for day in &days {
+        let count = if day.due > 0 { format!("({})", day.due) } else { String::new() };
+        let mut cell = format!("{:>3}", day.date.day()).normal();
+        if day.overdue {
+            cell = cell.red().bold();
+        } else if day.due > 0 {
+            cell = cell.yellow();
+        }
+        if day.date == today {
+            cell = cell.underline();
+        }
+        line.push_str(&format!("{}{:<4}", cell, count));
+        if day.date.weekday().num_days_from_monday() == 6 {
+            println!("{}", line);
+            line.clear();
+        }
+    }
+    if !line.is_empty() {
+        println!("{}", line);
+    }
+    let total: usize = days.iter().map(|day| day.due).sum();
+    println!("\n{} task(s) due this month. (n) = tasks due, red = overdue.", total);
+}
+
+pub fn display_agenda(task_manager: &mut TaskManager) {
+    clear_console();
+    let ids = print_agenda(task_manager, 7);
+    let choice = read_input("\nEnter task number to manage it");
+    if let Ok(index) = choice.parse::<usize>()
+        && index > 0
+        && let Some(&id) = ids.get(index - 1) {
+        task_actions(task_manager, id);
+    }
+}
+
+pub fn display_calendar(task_manager: &mut TaskManager) {
+    let mut month_date = Local::now().date_naive().with_day(1).unwrap();
+    loop {
+        clear_console();
+        print_month(task_manager, month_date);
+        let choice = read_input("\nEnter a day to see its tasks, 'N'/'P' for the next/previous month, 'A' for the week agenda, or nothing to go back");
+        match choice.to_uppercase().as_str() {
+            "" => return,
+            "N" => month_date = month_date.checked_add_months(Months::new(1)).unwrap_or(month_date),
+            "P" => month_date = month_date.checked_sub_months(Months::new(1)).unwrap_or(month_date),
+            "A" => display_agenda(task_manager),
+            day => match day.parse::<u32>().ok().and_then(|day| month_date.with_day(day)) {
+                Some(date) => display_day(task_manager, date),
+                None => {
+                    println!("Invalid day.");
+                    wait();
+                }
+            },
+        }
+    }
+}
+
+/// Lists the tasks due on a day and lets the user manage one or add another.
+fn display_day(task_manager: &mut TaskManager, date: NaiveDate) {
+    clear_console();
+    println!("{}", date.format("%A %Y-%m-%d").to_string().bold());
+    let tasks = tasks_by_day(task_manager).remove(&date).unwrap_or_default();
+    if tasks.is_empty() {
+        println!("Nothing due.");
+    }
+    for (i, task) in tasks.iter().enumerate() {
+        print_task_line(task_manager, i + 1, 0, task);
+    }
+    let ids: Vec<u32> = tasks.iter().map(|task| task.id()).collect();
+
+    let choice = read_input("\nEnter task number to manage it or 'A' to add a task due this day");
+    if choice.eq_ignore_ascii_case("A") {
+        clear_console();
+        match read_task_details() {
+            Ok(mut task) => {
+                // Keep a time if one was entered, otherwise due this day
+                if task.due_date() != Some(date) {
+                    task.set_due_date(date.format("%Y-%m-%d").to_string()).unwrap();
+                }
+                task_manager.add_task(task);
+                println!("Task added successfully.");
+            }
+            Err(e) => println!("Error: {}", e),
+        }
+        wait();
+    } else if let Ok(index) = choice.parse::<usize>()
+        && index > 0
+        && let Some(&id) = ids.get(index - 1) {
+        task_actions(task_manager, id);
+    }
+}
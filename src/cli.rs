@@ -0,0 +1,941 @@
+use chrono::{Local, NaiveDate};
+use clap::{Parser, Subcommand, ValueEnum};
+use colored::Colorize;
+use crate::console::display_task;
+use crate::json_output::{print_json, tasks_to_json, AgendaDayJson, AgendaJson, BoardColumnJson, CalendarDayJson, ProjectJson, ReportJson, SearchResultJson, TaskJson, ViewJson};
+use crate::backup::Backups;
+use crate::bulk::{apply, parse_selection, print_summary, BulkAction};
+use crate::board::{columns, print_board, Grouping};
+use crate::calendar::{agenda, month, print_agenda, print_month};
+use crate::config::Config;
+use crate::csv_handler::CsvHandler;
+use crate::query::Query;
+use crate::search::{print_result, search, SearchResult};
+use crate::status::Status;
+use crate::storage::{open_storage, persist_changes, Storage, StorageKind};
+use crate::task::Task;
+use crate::task_manager::TaskManager;
+use crate::views::{find_view, load_views, print_view, add_view, views_file, View, ViewColumn};
+
+// Exit codes
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_INVALID_INPUT: i32 = 2;
+pub const EXIT_NOT_FOUND: i32 = 3;
+
+/// Simple task manager. Starts the interactive menu when no command is given.
+#[derive(Parser)]
+#[command(name = "task-manager", version)]
+pub struct Cli {
+    /// Print machine-readable JSON instead of text (list, show, report, search, agenda,
+    /// calendar, board, view, project list and project show)
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Storage backend (overrides the config file)
+    #[arg(long, global = true, value_enum)]
+    pub storage: Option<StorageKind>,
+    /// Data file (overrides the config file)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub file: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Add a new task
+    Add {
+        name: String,
+        #[command(flatten)]
+        fields: TaskFields,
+        /// Make it a subtask of this task (ID or name)
+        #[arg(long)]
+        parent: Option<String>,
+        /// Wait for this task to be completed first (ID or name, repeatable)
+        #[arg(long = "depends-on", value_name = "TASK")]
+        depends_on: Vec<String>,
+    },
+    /// List pending tasks
+    List {
+        /// Include completed tasks
+        #[arg(long, conflicts_with = "completed")]
+        all: bool,
+        /// Only list completed tasks
+        #[arg(long)]
+        completed: bool,
+        /// Sort completed tasks by completion time, most recent first
+        #[arg(long, requires = "completed")]
+        by_completion_time: bool,
+        /// Only list tasks with any of these tags
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Only list tasks in this project
+        #[arg(long)]
+        project: Option<String>,
+        /// Only list pending tasks whose dependencies are all done
+        #[arg(long, conflicts_with_all = ["blocked", "all", "completed"])]
+        actionable: bool,
+        /// Only list tasks waiting for other tasks
+        #[arg(long, conflicts_with_all = ["all", "completed"])]
+        blocked: bool,
+        /// Only list tasks with any of these statuses (repeatable)
+        #[arg(long = "status", value_name = "STATUS", conflicts_with_all = ["all", "completed", "actionable", "blocked"])]
+        statuses: Vec<Status>,
+        /// Filter and sort with a query, e.g. "tag:work and due<+3d sort:-priority"
+        #[arg(long, short, value_name = "QUERY", conflicts_with_all = ["all", "completed", "actionable", "blocked", "statuses"])]
+        query: Option<String>,
+        /// Save the query as a named view (see the `view` command)
+        #[arg(long, value_name = "NAME", requires = "query")]
+        save_view: Option<String>,
+        /// Columns of the saved view, comma-separated (e.g. id,due,name,tags)
+        #[arg(long, value_name = "COLUMNS", requires = "save_view")]
+        columns: Option<String>,
+    },
+    /// Show all details of a task
+    Show {
+        /// Task ID or name
+        task: String,
+    },
+    /// Summarize pending work, overdue tasks and tasks due today
+    Report,
+    /// Show overdue tasks and the tasks due each day of the coming week
+    Agenda {
+        /// Number of days to show, starting today
+        #[arg(long, default_value_t = 7, value_parser = clap::value_parser!(u64).range(1..=3660))]
+        days: u64,
+    },
+    /// Show a month calendar with the number of tasks due each day
+    Calendar {
+        /// Month as YYYY-MM (default: this month)
+        month: Option<String>,
+    },
+    /// Show tasks as a kanban board
+    Board {
+        /// What the columns are
+        #[arg(long, value_enum, default_value_t = BoardColumns::Status)]
+        by: BoardColumns,
+        /// Tag to use as a column with --by tag (repeatable, in column order)
+        #[arg(long = "tag", value_name = "TAG", required_if_eq("by", "tag"))]
+        tags: Vec<String>,
+    },
+    /// Search task names, descriptions and tags, best matches first
+    Search {
+        /// Words to look for; each must match (typos and abbreviations allowed)
+        #[arg(required = true)]
+        words: Vec<String>,
+        /// Only show pending tasks
+        #[arg(long)]
+        pending: bool,
+    },
+    /// List projects with their task counts, or show or archive one
+    Project {
+        #[command(subcommand)]
+        action: ProjectAction,
+    },
+    /// List saved views, or show the tasks in one
+    View {
+        /// Name of the view to show
+        name: Option<String>,
+    },
+    /// Mark a task as completed
+    Done {
+        /// Task ID or name
+        task: String,
+    },
+    /// Change the status of a task (todo, in_progress, blocked, done, cancelled)
+    Status {
+        /// Task ID or name
+        task: String,
+        status: Status,
+    },
+    /// Set a done or cancelled task back to todo
+    Reopen {
+        /// Task ID or name
+        task: String,
+    },
+    /// Change fields of a task
+    Edit {
+        /// Task ID or name
+        task: String,
+        /// New name
+        #[arg(long)]
+        name: Option<String>,
+        #[command(flatten)]
+        fields: TaskFields,
+        /// Remove a tag (repeatable)
+        #[arg(long = "remove-tag", value_name = "TAG")]
+        remove_tags: Vec<String>,
+        /// Move under this task (ID or name); empty string moves it to the top level
+        #[arg(long)]
+        parent: Option<String>,
+        /// Wait for this task to be completed first (ID or name, repeatable)
+        #[arg(long = "depends-on", value_name = "TASK")]
+        depends_on: Vec<String>,
+        /// Stop waiting for this task (ID or name, repeatable)
+        #[arg(long = "no-depends-on", value_name = "TASK")]
+        no_depends_on: Vec<String>,
+    },
+    /// Delete a task
+    Delete {
+        /// Task ID or name
+        task: String,
+    },
+    /// Copy all tasks from a CSV file into the current storage
+    Import {
+        /// CSV file written by the csv backend
+        path: String,
+    },
+    /// Convert a task file from one storage format to another
+    Convert {
+        /// File to read
+        input: String,
+        /// File to write
+        output: String,
+        /// Format of the input file (guessed from the extension by default)
+        #[arg(long, value_enum)]
+        from: Option<StorageKind>,
+        /// Format of the output file (guessed from the extension by default)
+        #[arg(long, value_enum)]
+        to: Option<StorageKind>,
+        /// Overwrite the output file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Apply one action to several tasks at once
+    Bulk {
+        /// Task IDs, with ranges like 1-4,7
+        #[arg(long, required_unless_present = "query", conflicts_with = "query")]
+        ids: Option<String>,
+        /// Select the tasks matching a query instead
+        #[arg(short, long)]
+        query: Option<String>,
+        #[command(subcommand)]
+        action: BulkCommand,
+    },
+    /// List or restore backups of the data file
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+    /// Open the full-screen interface
+    Tui,
+}
+
+#[derive(Subcommand)]
+pub enum BackupAction {
+    /// List backups, newest first
+    List,
+    /// Replace the data file with a backup
+    Restore {
+        /// Number from `backup list` or the backup's file name
+        backup: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProjectAction {
+    /// List projects with their pending and completed task counts
+    List,
+    /// List every task in a project, including archived ones
+    Show { name: String },
+    /// Hide a project's tasks from the usual lists
+    Archive { name: String },
+    /// Bring back the tasks of an archived project
+    Unarchive { name: String },
+}
+
+#[derive(Subcommand)]
+pub enum BulkCommand {
+    /// Mark the tasks as done
+    Complete,
+    /// Delete the tasks and their subtasks
+    Delete,
+    /// Add a tag to the tasks
+    AddTag {
+        #[arg(value_parser = clap::builder::NonEmptyStringValueParser::new())]
+        tag: String,
+    },
+    /// Remove a tag from the tasks
+    RemoveTag {
+        #[arg(value_parser = clap::builder::NonEmptyStringValueParser::new())]
+        tag: String,
+    },
+    /// Set the priority of the tasks
+    Priority {
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=10))]
+        value: u8,
+    },
+    /// Move due dates by a number of days (negative moves them earlier)
+    ShiftDue {
+        #[arg(allow_negative_numbers = true)]
+        days: i64,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum BoardColumns {
+    Status,
+    Priority,
+    Tag,
+}
+
+#[derive(clap::Args)]
+pub struct TaskFields {
+    /// Description (empty string clears it)
+    #[arg(long)]
+    description: Option<String>,
+    /// Due date as YYYY-MM-DD, optionally with HH:MM and a UTC offset like +02:00,
+    /// or relative like tomorrow, next friday, in 3 days, +2w (empty string clears it)
+    #[arg(long)]
+    due: Option<String>,
+    /// Add a tag (repeatable)
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+    /// Priority from 0 to 10
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=10))]
+    priority: Option<u8>,
+    /// Repeat rule: daily, weekly[:mon,fri], monthly[:15], every:N or yearly (empty string clears it)
+    #[arg(long)]
+    repeat: Option<String>,
+    /// Project the task belongs to (empty string clears it)
+    #[arg(long)]
+    project: Option<String>,
+}
+
+impl TaskFields {
+    fn apply(&self, task: &mut Task) -> Result<(), String> {
+        if let Some(description) = &self.description {
+            if description.is_empty() {
+                task.clear_description();
+            } else {
+                task.set_description(description.clone());
+            }
+        }
+        if let Some(due) = &self.due {
+            if due.is_empty() {
+                task.clear_due_date();
+            } else {
+                task.set_due_date(due.clone())?;
+            }
+        }
+        for tag in &self.tags {
+            let tag = tag.trim();
+            if !tag.is_empty() {
+                task.add_tag(tag.to_string());
+            }
+        }
+        if let Some(priority) = self.priority {
+            task.set_priority(priority)?;
+        }
+        if let Some(repeat) = &self.repeat {
+            if repeat.is_empty() {
+                task.clear_recurrence();
+            } else {
+                task.set_recurrence(repeat.clone())?;
+            }
+        }
+        if let Some(project) = &self.project {
+            let project = project.trim();
+            if project.is_empty() {
+                task.clear_project();
+            } else {
+                task.set_project(project.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs a single command and returns the process exit code.
+pub fn run(command: Command, json: bool, task_manager: &mut TaskManager, storage: &dyn Storage, config: &Config) -> i32 {
+    match command {
+        Command::Add { name, fields, parent, depends_on } => {
+            if name.trim().is_empty() {
+                eprintln!("Error: Task name is empty");
+                return EXIT_INVALID_INPUT;
+            }
+            let mut task = Task::new(name);
+            if let Err(e) = fields.apply(&mut task) {
+                eprintln!("Error: {}", e);
+                return EXIT_INVALID_INPUT;
+            }
+            if let Some(parent) = parent {
+                let Some(parent_id) = resolve_task(task_manager, &parent) else {
+                    return not_found(&parent);
+                };
+                task.set_parent(Some(parent_id));
+            }
+            let mut dependencies = Vec::new();
+            for dependency in &depends_on {
+                match resolve_task(task_manager, dependency) {
+                    Some(dependency_id) => dependencies.push(dependency_id),
+                    None => return not_found(dependency),
+                }
+            }
+            let id = task_manager.add_task(task);
+            for dependency in dependencies {
+                // A new task can't be part of a cycle yet
+                task_manager.add_dependency(id, dependency).unwrap();
+            }
+            if !save(persist_changes(task_manager, storage)) {
+                return EXIT_FAILURE;
+            }
+            println!("{}", id);
+            EXIT_SUCCESS
+        }
+        Command::List { all, completed, by_completion_time, tags, project, actionable, blocked, statuses, query, save_view, columns } => {
+            if let (Some(name), Some(query)) = (save_view, &query) {
+                let saved = View::from_query(name.clone(), query).and_then(|mut view| {
+                    if let Some(columns) = &columns {
+                        view.columns = columns.split(',').map(ViewColumn::from_name).collect::<Result<_, _>>()?;
+                    }
+                    add_view(&views_file(&config.data_file()), view)
+                });
+                match saved {
+                    Ok(()) => eprintln!("View '{}' saved.", name),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        return EXIT_INVALID_INPUT;
+                    }
+                }
+            }
+            // A query decides for itself which statuses to include
+            let tasks: Vec<&Task> = if let Some(query) = query {
+                match Query::parse(&query) {
+                    Ok(parsed) => parsed.run(task_manager),
+                    Err(e) => {
+                        eprintln!("Invalid query:\n{}", e.explain(&query));
+                        return EXIT_INVALID_INPUT;
+                    }
+                }
+            } else if !statuses.is_empty() {
+                task_manager.list_tasks_with_status(&statuses)
+            } else if completed && by_completion_time {
+                task_manager.list_completed_tasks_by_completion_time()
+            } else if completed {
+                task_manager.list_completed_tasks()
+            } else if actionable {
+                task_manager.list_actionable_tasks()
+            } else if blocked {
+                task_manager.list_blocked_tasks()
+            } else if all {
+                task_manager.get_all_tasks().into_iter().filter(|task| !task.is_archived()).collect()
+            } else {
+                let mut tasks = task_manager.get_all_pending_tasks_with_due_date();
+                tasks.extend(task_manager.get_all_pending_tasks_without_due_date());
+                tasks
+            };
+            let tasks: Vec<&Task> = tasks.into_iter()
+                .filter(|task| tags.is_empty() || task.tags().iter().any(|tag| tags.contains(tag)))
+                .filter(|task| project.is_none() || task.project() == project.as_deref())
+                .collect();
+            if json {
+                print_json(&tasks_to_json(task_manager, &tasks));
+            } else {
+                for task in tasks {
+                    print_task_row(task_manager, task);
+                }
+            }
+            EXIT_SUCCESS
+        }
+        Command::Search { words, pending } => {
+            let results: Vec<SearchResult> = search(task_manager, &words.join(" ")).into_iter()
+                .filter(|result| !pending || result.task.is_pending())
+                .collect();
+            if json {
+                let results: Vec<SearchResultJson> = results.iter()
+                    .map(|result| SearchResultJson {
+                        score: result.score,
+                        task: TaskJson::new(task_manager, result.task),
+                    })
+                    .collect();
+                print_json(&results);
+            } else {
+                for result in &results {
+                    print_result(None, result);
+                }
+            }
+            if results.is_empty() { EXIT_NOT_FOUND } else { EXIT_SUCCESS }
+        }
+        Command::Project { action } => match &action {
+            ProjectAction::List => {
+                let projects = task_manager.projects();
+                if json {
+                    let projects: Vec<ProjectJson> = projects.iter()
+                        .map(|name| ProjectJson {
+                            name,
+                            pending: task_manager.project_pending_count(name),
+                            completed: task_manager.project_completed_count(name),
+                            archived: task_manager.is_project_archived(name),
+                        })
+                        .collect();
+                    print_json(&projects);
+                } else {
+                    for name in projects {
+                        print!("{} ({} pending, {} completed)", name,
+                            task_manager.project_pending_count(name), task_manager.project_completed_count(name));
+                        if task_manager.is_project_archived(name) {
+                            print!(" {}", "[archived]".dimmed());
+                        }
+                        println!();
+                    }
+                }
+                EXIT_SUCCESS
+            }
+            ProjectAction::Show { name } => {
+                let tasks = task_manager.tasks_in_project(name);
+                if tasks.is_empty() {
+                    eprintln!("Error: No project named '{}'", name);
+                    return EXIT_NOT_FOUND;
+                }
+                if json {
+                    print_json(&tasks_to_json(task_manager, &tasks));
+                } else {
+                    for task in tasks {
+                        print_task_row(task_manager, task);
+                    }
+                }
+                EXIT_SUCCESS
+            }
+            ProjectAction::Archive { name } | ProjectAction::Unarchive { name } => {
+                let archive = matches!(action, ProjectAction::Archive { .. });
+                let count = match task_manager.set_project_archived(name, archive) {
+                    Ok(count) => count,
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        return EXIT_NOT_FOUND;
+                    }
+                };
+                if !save(persist_changes(task_manager, storage)) {
+                    return EXIT_FAILURE;
+                }
+                println!("{} {} task(s) in '{}'", if archive { "Archived" } else { "Unarchived" }, count, name);
+                EXIT_SUCCESS
+            }
+        },
+        Command::View { name } => {
+            let views = match load_views(&views_file(&config.data_file())) {
+                Ok(views) => views,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    return EXIT_FAILURE;
+                }
+            };
+            let Some(name) = name else {
+                if json {
+                    let views: Vec<ViewJson> = views.iter()
+                        .map(|view| ViewJson {
+                            name: &view.name,
+                            filter: &view.filter,
+                            sort: &view.sort,
+                            columns: view.columns.iter().map(|column| column.name()).collect(),
+                        })
+                        .collect();
+                    print_json(&views);
+                } else {
+                    for view in &views {
+                        println!("{}: {}{}", view.name, view.filter,
+                            if view.sort.is_empty() { String::new() } else { format!(" sort:{}", view.sort) });
+                    }
+                }
+                return EXIT_SUCCESS;
+            };
+            let Some(view) = find_view(&views, &name) else {
+                eprintln!("Error: View not found: {}", name);
+                return EXIT_NOT_FOUND;
+            };
+            match view.tasks(task_manager) {
+                Ok(tasks) if json => print_json(&tasks_to_json(task_manager, &tasks)),
+                Ok(tasks) => print_view(view, &tasks, false),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    return EXIT_INVALID_INPUT;
+                }
+            }
+            EXIT_SUCCESS
+        }
+        Command::Show { task } => {
+            match resolve_task(task_manager, &task) {
+                Some(id) => {
+                    let task = task_manager.get_task(id).unwrap();
+                    if json {
+                        print_json(&TaskJson::new(task_manager, task));
+                    } else {
+                        display_task(task_manager, task);
+                    }
+                    EXIT_SUCCESS
+                }
+                None => not_found(&task),
+            }
+        }
+        Command::Report => {
+            let pending = task_manager.get_all_pending_tasks_with_due_date();
+            let overdue: Vec<&Task> = pending.iter().copied().filter(|task| task.is_overdue()).collect();
+            let due_today: Vec<&Task> = pending.iter().copied().filter(|task| task.is_due_today()).collect();
+            if json {
+                print_json(&ReportJson {
+                    pending: task_manager.pending_task_count(),
+                    completed: task_manager.list_completed_tasks().len(),
+                    overdue: tasks_to_json(task_manager, &overdue),
+                    due_today: tasks_to_json(task_manager, &due_today),
+                });
+            } else {
+                println!("Pending: {}", task_manager.pending_task_count());
+                println!("Completed: {}", task_manager.list_completed_tasks().len());
+                println!("Overdue: {}", overdue.len());
+                for task in overdue {
+                    print_task_row(task_manager, task);
+                }
+                println!("Due today: {}", due_today.len());
+                for task in due_today {
+                    print_task_row(task_manager, task);
+                }
+            }
+            EXIT_SUCCESS
+        }
+        Command::Agenda { days } => {
+            if json {
+                let agenda = agenda(task_manager, days);
+                print_json(&AgendaJson {
+                    overdue: tasks_to_json(task_manager, &agenda.overdue),
+                    days: agenda.days.iter()
+                        .map(|(date, tasks)| AgendaDayJson {
+                            date: date.format("%Y-%m-%d").to_string(),
+                            tasks: tasks_to_json(task_manager, tasks),
+                        })
+                        .collect(),
+                });
+            } else {
+                print_agenda(task_manager, days);
+            }
+            EXIT_SUCCESS
+        }
+        Command::Calendar { month: month_input } => {
+            let month_date = match month_input {
+                Some(input) => match NaiveDate::parse_from_str(&format!("{}-01", input.trim()), "%Y-%m-%d") {
+                    Ok(date) => date,
+                    Err(_) => {
+                        eprintln!("Error: Invalid month '{}'. Use YYYY-MM.", input);
+                        return EXIT_INVALID_INPUT;
+                    }
+                },
+                None => Local::now().date_naive(),
+            };
+            if json {
+                let days: Vec<CalendarDayJson> = month(task_manager, month_date).into_iter()
+                    .map(|day| CalendarDayJson {
+                        date: day.date.format("%Y-%m-%d").to_string(),
+                        due: day.due,
+                        overdue: day.overdue,
+                    })
+                    .collect();
+                print_json(&days);
+            } else {
+                print_month(task_manager, month_date);
+            }
+            EXIT_SUCCESS
+        }
+        Command::Board { by, tags } => {
+            let grouping = match by {
+                BoardColumns::Status => Grouping::Status,
+                BoardColumns::Priority => Grouping::Priority,
+                BoardColumns::Tag => Grouping::Tags(tags),
+            };
+            let board = columns(task_manager, &grouping);
+            if json {
+                let board: Vec<BoardColumnJson> = board.into_iter()
+                    .map(|column| BoardColumnJson {
+                        tasks: tasks_to_json(task_manager, &column.tasks),
+                        title: column.title,
+                    })
+                    .collect();
+                print_json(&board);
+            } else {
+                print_board(task_manager, &board);
+            }
+            EXIT_SUCCESS
+        }
+        Command::Done { task } => {
+            let Some(id) = resolve_task(task_manager, &task) else {
+                return not_found(&task);
+            };
+            if task_manager.is_blocked(task_manager.get_task(id).unwrap()) {
+                eprintln!("Warning: completing a task that is still blocked");
+            }
+            change_status(task_manager, storage, id, Status::Done)
+        }
+        Command::Status { task, status } => {
+            let Some(id) = resolve_task(task_manager, &task) else {
+                return not_found(&task);
+            };
+            change_status(task_manager, storage, id, status)
+        }
+        Command::Reopen { task } => {
+            let Some(id) = resolve_task(task_manager, &task) else {
+                return not_found(&task);
+            };
+            change_status(task_manager, storage, id, Status::Todo)
+        }
+        Command::Edit { task, name, fields, remove_tags, parent, depends_on, no_depends_on } => {
+            let Some(id) = resolve_task(task_manager, &task) else {
+                return not_found(&task);
+            };
+            let mut updated = task_manager.get_task(id).unwrap().clone();
+            if let Some(name) = name {
+                if name.trim().is_empty() {
+                    eprintln!("Error: Task name is empty");
+                    return EXIT_INVALID_INPUT;
+                }
+                updated.set_name(name);
+            }
+            for tag in &remove_tags {
+                updated.remove_tag(tag);
+            }
+            if let Err(e) = fields.apply(&mut updated) {
+                eprintln!("Error: {}", e);
+                return EXIT_INVALID_INPUT;
+            }
+            task_manager.update_task(updated);
+            if let Some(parent) = parent {
+                let parent_id = if parent.is_empty() {
+                    None
+                } else {
+                    match resolve_task(task_manager, &parent) {
+                        Some(parent_id) => Some(parent_id),
+                        None => return not_found(&parent),
+                    }
+                };
+                if let Err(e) = task_manager.set_parent(id, parent_id) {
+                    eprintln!("Error: {}", e);
+                    return EXIT_INVALID_INPUT;
+                }
+            }
+            for dependency in &no_depends_on {
+                let Some(dependency_id) = resolve_task(task_manager, dependency) else {
+                    return not_found(dependency);
+                };
+                task_manager.remove_dependency(id, dependency_id);
+            }
+            for dependency in &depends_on {
+                let Some(dependency_id) = resolve_task(task_manager, dependency) else {
+                    return not_found(dependency);
+                };
+                if let Err(e) = task_manager.add_dependency(id, dependency_id) {
+                    eprintln!("Error: {}", e);
+                    return EXIT_INVALID_INPUT;
+                }
+            }
+            if save(persist_changes(task_manager, storage)) { EXIT_SUCCESS } else { EXIT_FAILURE }
+        }
+        Command::Delete { task } => {
+            let Some(id) = resolve_task(task_manager, &task) else {
+                return not_found(&task);
+            };
+            task_manager.remove_task(id);
+            if save(persist_changes(task_manager, storage)) { EXIT_SUCCESS } else { EXIT_FAILURE }
+        }
+        Command::Import { path } => {
+            let tasks = match CsvHandler::new(path.clone()).load_tasks() {
+                Ok(tasks) => tasks,
+                Err(e) => {
+                    eprintln!("Error reading {}: {}", path, e);
+                    return EXIT_FAILURE;
+                }
+            };
+            let (imported, skipped) = task_manager.import_tasks(tasks);
+            if !save(persist_changes(task_manager, storage)) {
+                return EXIT_FAILURE;
+            }
+            println!("Imported {} tasks from {}", imported, path);
+            if skipped > 0 {
+                println!("Skipped {} tasks that were already imported", skipped);
+            }
+            EXIT_SUCCESS
+        }
+        Command::Convert { input, output, from, to, force } => {
+            let (Some(from), Some(to)) = (from.or(StorageKind::from_path(&input)), to.or(StorageKind::from_path(&output))) else {
+                eprintln!("Error: Cannot tell the file format from the extension, use --from/--to");
+                return EXIT_INVALID_INPUT;
+            };
+            if std::path::Path::new(&output).exists() && !force {
+                eprintln!("Error: {} already exists (use --force to overwrite)", output);
+                return EXIT_INVALID_INPUT;
+            }
+            let tasks = match open_storage(from, input.clone()).and_then(|source| source.load_tasks()) {
+                Ok(tasks) => tasks,
+                Err(e) => {
+                    eprintln!("Error reading {}: {}", input, e);
+                    return EXIT_FAILURE;
+                }
+            };
+            // Assign IDs to tasks from files that predate them
+            let mut converted = TaskManager::new();
+            converted.load_tasks(tasks);
+            let result = open_storage(to, output.clone()).and_then(|target| {
+                target.save_tasks(&converted.get_all_tasks())?;
+                let history = open_storage(from, input.clone())?.load_history()?;
+                target.append_history(&history)
+            });
+            if !save(result) {
+                return EXIT_FAILURE;
+            }
+            println!("Converted {} tasks from {} to {}", converted.get_all_tasks().len(), input, output);
+            EXIT_SUCCESS
+        }
+        Command::Bulk { ids, query, action } => {
+            let ids: Vec<u32> = if let Some(query) = query {
+                match Query::parse(&query) {
+                    Ok(parsed) => parsed.run(task_manager).iter().map(|task| task.id()).collect(),
+                    Err(e) => {
+                        eprintln!("Invalid query:\n{}", e.explain(&query));
+                        return EXIT_INVALID_INPUT;
+                    }
+                }
+            } else {
+                let ids = ids.unwrap_or_default();
+                let highest = task_manager.get_all_tasks().iter().map(|task| task.id()).max().unwrap_or(0);
+                match parse_selection(&ids, highest as usize) {
+                    // Ranges may span IDs of deleted tasks
+                    Ok(numbers) => numbers.into_iter()
+                        .map(|number| number as u32)
+                        .filter(|id| task_manager.get_task(*id).is_some())
+                        .collect(),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        return EXIT_INVALID_INPUT;
+                    }
+                }
+            };
+            if ids.is_empty() {
+                eprintln!("Error: No tasks selected");
+                return EXIT_NOT_FOUND;
+            }
+            let action = match action {
+                BulkCommand::Complete => BulkAction::Complete,
+                BulkCommand::Delete => BulkAction::Delete,
+                BulkCommand::AddTag { tag } => BulkAction::AddTag(tag.trim().to_string()),
+                BulkCommand::RemoveTag { tag } => BulkAction::RemoveTag(tag.trim().to_string()),
+                BulkCommand::Priority { value } => BulkAction::SetPriority(value),
+                BulkCommand::ShiftDue { days } => BulkAction::ShiftDue(days),
+            };
+            let summary = apply(task_manager, &ids, &action);
+            if !save(persist_changes(task_manager, storage)) {
+                return EXIT_FAILURE;
+            }
+            print_summary(&summary);
+            EXIT_SUCCESS
+        }
+        Command::Backup { action } => {
+            let backups = Backups::new(&config.data_file(), config.backups);
+            let list = match backups.list() {
+                Ok(list) => list,
+                Err(e) => {
+                    eprintln!("Error listing backups: {}", e);
+                    return EXIT_FAILURE;
+                }
+            };
+            match action {
+                BackupAction::List => {
+                    for (i, backup) in list.iter().enumerate() {
+                        println!("{:>3}. {}", i + 1, backup.display());
+                    }
+                    EXIT_SUCCESS
+                }
+                BackupAction::Restore { backup } => {
+                    let selected = match backup.parse::<usize>() {
+                        Ok(index) if index > 0 => list.get(index - 1),
+                        _ => list.iter().find(|path| {
+                            path.file_name().is_some_and(|name| name.to_string_lossy() == backup)
+                                || path.to_string_lossy() == backup
+                        }),
+                    };
+                    let Some(selected) = selected else {
+                        eprintln!("Error: No backup matching '{}'", backup);
+                        return EXIT_NOT_FOUND;
+                    };
+                    if !save(backups.restore(selected)) {
+                        return EXIT_FAILURE;
+                    }
+                    println!("Restored {}", selected.display());
+                    EXIT_SUCCESS
+                }
+            }
+        }
+        Command::Tui => unreachable!("the full-screen interface is started by main"),
+    }
+}
+
+fn change_status(task_manager: &mut TaskManager, storage: &dyn Storage, id: u32, status: Status) -> i32 {
+    let change = match task_manager.set_status(id, status) {
+        Ok(change) => change,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return EXIT_INVALID_INPUT;
+        }
+    };
+    if !save(persist_changes(task_manager, storage)) {
+        return EXIT_FAILURE;
+    }
+    if let Some(next) = change.next_occurrence.and_then(|id| task_manager.get_task(id)) {
+        println!("Next occurrence {} due {}", next.id(), next.due_as_local_str().unwrap_or_default());
+    }
+    for unblocked in change.unblocked.iter().filter_map(|id| task_manager.get_task(*id)) {
+        println!("Unblocked {} {}", unblocked.id(), unblocked.name());
+    }
+    EXIT_SUCCESS
+}
+
+/// Finds a task by ID, falling back to an exact name match.
+fn resolve_task(task_manager: &TaskManager, reference: &str) -> Option<u32> {
+    if let Ok(id) = reference.parse::<u32>()
+        && task_manager.get_task(id).is_some() {
+        return Some(id);
+    }
+    task_manager.find_task_by_name(reference).map(|task| task.id())
+}
+
+fn not_found(reference: &str) -> i32 {
+    eprintln!("Error: No task matching '{}'", reference);
+    EXIT_NOT_FOUND
+}
+
+fn save(result: std::io::Result<()>) -> bool {
+    match result {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!("Error saving tasks: {}", e);
+            false
+        }
+    }
+}
+
+fn print_task_row(task_manager: &TaskManager, task: &Task) {
+    print!("{:>4} ", task.id());
+    task.print_priority();
+    match task.due_as_local_str() {
+        Some(due) if task.is_overdue() => print!(" {}", format!("{:10}", due).red()),
+        Some(due) => print!(" {:10}", due),
+        None => print!(" {:10}", ""),
+    }
+    if task.is_pending() {
+        print!(" {}", task.name());
+    } else {
+        print!(" {}", task.name().strikethrough());
+    }
+    if task.status() != Status::Todo {
+        print!(" [{}]", task.status());
+    }
+    if task.recurrence().is_some() {
+        print!(" ↻");
+    }
+    if let Some(project) = task.project() {
+        print!(" {}", format!("@{}", project).cyan());
+    }
+    if let Some((done, total)) = task_manager.subtask_progress(task.id()) {
+        print!(" ({}/{})", done, total);
+    }
+    if task.status() != Status::Blocked && task_manager.is_blocked(task) {
+        print!(" {}", "[blocked]".dimmed());
+    }
+    println!();
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::Path;
+use clap::ValueEnum;
+use crate::status::{Status, Workflow};
+use crate::storage::StorageKind;
+
+pub const CONFIG_FILE: &str = "task-manager.conf";
+
+/// Settings read from `task-manager.conf`, one `key = value` per line.
+/// Command-line flags take precedence over anything set here.
+pub struct Config {
+    pub storage: StorageKind,
+    pub file: Option<String>,
+    /// Number of backups of the data file to keep (0 disables them)
+    pub backups: usize,
+    /// Save after every change instead of only on exit
+    pub autosave: bool,
+    /// Allowed status changes, overridden per status with `transitions.<status>`
+    pub workflow: Workflow,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config {
+            storage: StorageKind::Csv,
+            file: None,
+            backups: 5,
+            autosave: true,
+            workflow: Workflow::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Config, String> {
+        let mut config = Config::new();
+        if !Path::new(path).exists() {
+            return Ok(config);
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("{}:{}: expected 'key = value'", path, number + 1));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "storage" => {
+                    config.storage = StorageKind::from_str(value, true)
+                        .map_err(|_| format!("{}:{}: unknown storage '{}'", path, number + 1, value))?;
+                }
+                "file" => config.file = Some(value.to_string()),
+                "backups" => {
+                    config.backups = value.parse()
+                        .map_err(|_| format!("{}:{}: backups must be a number", path, number + 1))?;
+                }
+                "autosave" => {
+                    config.autosave = value.parse()
+                        .map_err(|_| format!("{}:{}: autosave must be true or false", path, number + 1))?;
+                }
+                key if key.starts_with("transitions.") => {
+                    let from: Status = key["transitions.".len()..].parse()
+                        .map_err(|e| format!("{}:{}: {}", path, number + 1, e))?;
+                    let mut to = Vec::new();
+                    for status in value.split(',').map(str::trim).filter(|status| !status.is_empty()) {
+                        to.push(status.parse::<Status>()
+                            .map_err(|e| format!("{}:{}: {}", path, number + 1, e))?);
+                    }
+                    config.workflow.set_transitions(from, to);
+                }
+                other => return Err(format!("{}:{}: unknown setting '{}'", path, number + 1, other)),
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn data_file(&self) -> String {
+        self.file.clone().unwrap_or_else(|| self.storage.default_file().to_string())
+    }
+}
@@ -1,12 +1,16 @@
 use std::io;
-use crate::task::Task;
-use crate::task_manager::TaskManager;
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use crate::bulk;
+use crate::query::Query;
+use crate::status::Status;
+use crate::task::{Due, Task};
+use crate::task_manager::{StatusChange, TaskManager};
 use colored::Colorize;
 
 pub fn clear_console() {
     if cfg!(target_os = "windows") {
         std::process::Command::new("cmd")
-            .args(&["/C", "cls"])
+            .args(["/C", "cls"])
             .status()
             .expect("Failed to clear console");
     } else {
@@ -39,12 +43,13 @@ pub fn read_task_details() -> Result<Task, String> {
 
     // Due date
     loop {
-        let due_date = read_input("Enter due date (YYYY-MM-DD) (optional):");
+        let due_date = read_input("Enter due date (YYYY-MM-DD [HH:MM], or e.g. tomorrow, next friday, in 3 days) (optional):");
         if due_date.is_empty() {
             break;
         }
-        match task.set_due_date(due_date) {
-            Ok(_) => break,
+        match task.set_due_date(due_date.clone()) {
+            Ok(_) if confirm_due_date(&due_date, &task) => break,
+            Ok(_) => task.clear_due_date(),
             Err(e) => println!("Error setting due date: {}", e),
         }
     }
@@ -57,6 +62,12 @@ pub fn read_task_details() -> Result<Task, String> {
         }
     }
 
+    // Project
+    let project = read_input("Enter project (optional):");
+    if !project.is_empty() {
+        task.set_project(project);
+    }
+
     // Priority
     loop {
         let priority = read_input("Enter priority (0-10) (default: 5):");
@@ -71,42 +82,185 @@ pub fn read_task_details() -> Result<Task, String> {
         }
     }
 
+    // Recurrence
+    loop {
+        let recurrence = read_input("Repeat (daily, weekly[:mon,fri], monthly[:15], every:N, yearly) (optional):");
+        if recurrence.is_empty() {
+            break;
+        }
+        match task.set_recurrence(recurrence) {
+            Ok(_) => break,
+            Err(e) => println!("Error setting repeat rule: {}", e),
+        }
+    }
+
     Ok(task)
 }
 
-pub fn display_task(task: &Task) {
+/// Shows what a relative date resolved to and asks whether to keep it.
+fn confirm_due_date(input: &str, task: &Task) -> bool {
+    match (task.due_date_as_str(), task.due_as_local_str(), task.due_date()) {
+        (Some(resolved), Some(local), Some(date)) if resolved != input.trim() => {
+            let confirm = read_input(&format!("Due {} ({}). Use this date? (y/n)", local, date.format("%A")));
+            confirm.to_lowercase() == "y"
+        }
+        _ => true,
+    }
+}
+
+pub fn display_task(task_manager: &TaskManager, task: &Task) {
+    println!("ID: {}", task.id());
     println!("Name: {}", task.name().bold());
+    if let Some(parent) = task.parent().and_then(|parent| task_manager.get_task(parent)) {
+        println!("Subtask of: {} (ID {})", parent.name(), parent.id());
+    }
     if let Some(description) = task.description() {
         println!("Description: {}", description);
     }
-    if let Some(due_date) = task.due_date() {
-        println!("Due Date: {}", due_date);
+    if let Some(due) = task.due_as_local_str() {
+        match task.due() {
+            // Also show the time as entered if it was in another zone
+            Some(Due::At(at)) if at.offset().local_minus_utc() != at.with_timezone(&Local).offset().local_minus_utc() => {
+                println!("Due Date: {} ({})", due, task.due_date_as_str().unwrap());
+            }
+            _ => println!("Due Date: {}", due),
+        }
     }
     if !task.tags().is_empty() {
         println!("Tags: {}", task.tags().iter().cloned().collect::<Vec<String>>().join(", "));
     }
+    if let Some(project) = task.project() {
+        println!("Project: {}{}", project, if task.is_archived() { " (archived)" } else { "" });
+    }
+    if let Some(recurrence) = task.recurrence() {
+        println!("Repeats: {}", recurrence);
+    }
+    if !task.dependencies().is_empty() {
+        let dependencies: Vec<String> = task.dependencies().iter()
+            .filter_map(|id| task_manager.get_task(*id))
+            .map(|dependency| {
+                format!("{} (ID {}, {})", dependency.name(), dependency.id(), dependency.status().label().to_lowercase())
+            })
+            .collect();
+        println!("Depends on: {}", dependencies.join(", "));
+        if !task_manager.blocking_tasks(task).is_empty() {
+            println!("{}", "Blocked until its dependencies are completed.".yellow());
+        }
+    }
     task.print_priority();
-    println!("\nCompleted: {}", task.completed());
+    println!("\nStatus: {}", task.status().label());
+    println!("Created: {}", local_time(task.created_at()));
+    println!("Last changed: {}", local_time(task.updated_at()));
+    if let Some(completed_at) = task.completed_at() {
+        println!("Completed: {} (took {})", local_time(completed_at), format_duration(task.cycle_time().unwrap()));
+    }
+    if let Some((done, total)) = task_manager.subtask_progress(task.id()) {
+        println!("Subtasks: {}/{} done", done, total);
+        print_subtask_tree(task_manager, task.id(), 1);
+    }
+    print_history(task_manager, task.id());
 }
 
-pub fn display_completed_tasks(task_manager: &TaskManager) {
-    let completed_tasks = task_manager.list_completed_tasks();
-    if completed_tasks.is_empty() {
-        println!("No completed tasks.");
-        wait();
+/// Most recent changes listed in a task's history
+const HISTORY_SHOWN: usize = 15;
+
+fn print_history(task_manager: &TaskManager, id: u32) {
+    let history = task_manager.history_of(id);
+    if history.is_empty() {
         return;
     }
-    println!("Completed Tasks:");
-    for (i, task) in completed_tasks.iter().enumerate() {
-        println!("{}. {}", i + 1, task.name());
+    println!("History:");
+    if history.len() > HISTORY_SHOWN {
+        println!("  {}", format!("({} earlier changes)", history.len() - HISTORY_SHOWN).dimmed());
+    }
+    let shown_value = |value: &str| if value.is_empty() { "(none)".to_string() } else { format!("'{}'", value) };
+    for entry in history.iter().skip(history.len().saturating_sub(HISTORY_SHOWN)) {
+        let change = match entry.field.as_str() {
+            "created" => format!("created as {}", shown_value(&entry.new_value)),
+            "deleted" => "deleted".to_string(),
+            field => format!("{}: {} → {}", field, shown_value(&entry.old_value), shown_value(&entry.new_value)),
+        };
+        println!("  {} {} {}", local_time(entry.timestamp).dimmed(), entry.user.cyan(), change);
+    }
+}
+
+fn print_subtask_tree(task_manager: &TaskManager, id: u32, depth: usize) {
+    for subtask in task_manager.subtasks_of(id) {
+        println!("{}{} {} (ID {})", "  ".repeat(depth), status_marker(subtask.status()), subtask.name(), subtask.id());
+        print_subtask_tree(task_manager, subtask.id(), depth + 1);
+    }
+}
+
+pub fn local_time(timestamp: DateTime<Utc>) -> String {
+    timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+}
+
+pub fn format_duration(duration: TimeDelta) -> String {
+    if duration.num_days() > 0 {
+        format!("{}d {}h", duration.num_days(), duration.num_hours() % 24)
+    } else if duration.num_hours() > 0 {
+        format!("{}h {}m", duration.num_hours(), duration.num_minutes() % 60)
+    } else {
+        format!("{}m", duration.num_minutes().max(0))
+    }
+}
+
+pub fn status_marker(status: Status) -> &'static str {
+    match status {
+        Status::Todo => "[ ]",
+        Status::InProgress => "[~]",
+        Status::Blocked => "[!]",
+        Status::Done => "[x]",
+        Status::Cancelled => "[-]",
     }
+}
 
-    println!("\nTotal completed tasks: {}", completed_tasks.len());
-    wait();
+pub fn display_completed_tasks(task_manager: &mut TaskManager) {
+    let mut by_completion_time = false;
+    let selected = loop {
+        let completed_tasks = if by_completion_time {
+            task_manager.list_completed_tasks_by_completion_time()
+        } else {
+            task_manager.list_completed_tasks()
+        };
+        if completed_tasks.is_empty() {
+            println!("No completed tasks.");
+            wait();
+            return;
+        }
+        println!("Completed Tasks:");
+        for (i, task) in completed_tasks.iter().enumerate() {
+            match task.completed_at() {
+                Some(completed_at) => println!("{}. {} (completed {})", i + 1, task.name(), local_time(completed_at)),
+                None => println!("{}. {}", i + 1, task.name()),
+            }
+        }
+        println!("\nTotal completed tasks: {}", completed_tasks.len());
+
+        let sort_option = if by_completion_time { "'I' to sort by ID" } else { "'T' to sort by completion time" };
+        let choice = read_input(&format!("\nEnter task number to reopen it or {}", sort_option));
+        if choice.eq_ignore_ascii_case("T") || choice.eq_ignore_ascii_case("I") {
+            by_completion_time = choice.eq_ignore_ascii_case("T");
+            clear_console();
+            continue;
+        }
+        break match choice.parse::<usize>() {
+            Ok(index) if index > 0 => completed_tasks.get(index - 1).map(|task| task.id()),
+            _ => None,
+        };
+    };
+
+    if let Some(id) = selected {
+        match task_manager.reopen_task(id) {
+            Ok(_) => println!("Task reopened."),
+            Err(e) => println!("Error: {}", e),
+        }
+        wait();
+    }
 }
 
-pub fn edit_task(task_manager: &mut TaskManager, name: &str) {
-    if let Some(task) = task_manager.get_task(name) {
+pub fn edit_task(task_manager: &mut TaskManager, id: u32) {
+    if let Some(task) = task_manager.get_task(id) {
         let task = task.clone();
         clear_console();
 
@@ -139,7 +293,16 @@ pub fn edit_task(task_manager: &mut TaskManager, name: &str) {
         let due_date = if due_date_input.is_empty() {
             current_due_date
         } else {
-            due_date_input
+            // Resolve relative dates now so the preview matches what is saved
+            let mut preview = task.clone();
+            match preview.set_due_date(due_date_input.clone()) {
+                Ok(_) if confirm_due_date(&due_date_input, &preview) => preview.due_date_as_str().unwrap(),
+                Ok(_) => current_due_date,
+                Err(e) => {
+                    println!("Invalid due date: {}", e);
+                    return;
+                }
+            }
         };
 
         // Tags
@@ -155,6 +318,11 @@ pub fn edit_task(task_manager: &mut TaskManager, name: &str) {
                 .collect()
         };
 
+        // Project
+        clear_console();
+        println!("Editing task: {}\n(Skip fields to keep current values, '-' to remove it from its project)", task.name());
+        let project_input = read_input(&format!("Enter new project [{}]: ", task.project().unwrap_or_default()));
+
         // Priority
         clear_console();
         println!("Editing task: {}\n(Skip fields to keep current values)", task.name());
@@ -171,19 +339,29 @@ pub fn edit_task(task_manager: &mut TaskManager, name: &str) {
             }
         };
 
-        // Create updated task
-        let mut new_task = Task::new(new_name);
+        // Recurrence
+        clear_console();
+        println!("Editing task: {}\n(Skip fields to keep current values, '-' to stop repeating)", task.name());
+        let current_recurrence = task.recurrence_as_str().unwrap_or_default();
+        let recurrence_input = read_input(&format!("Enter new repeat rule [{}]: ", current_recurrence));
+
+        // Update a copy so the ID and completion state are kept
+        let mut new_task = task.clone();
+        new_task.set_name(new_name);
         new_task.set_description(description);
 
-        if !due_date.is_empty() {
-            if let Err(e) = new_task.set_due_date(due_date) {
-                println!("Invalid due date: {}", e);
-                return;
-            }
+        if !due_date.is_empty()
+            && let Err(e) = new_task.set_due_date(due_date) {
+            println!("Invalid due date: {}", e);
+            return;
         }
 
-        for tag in tags {
-            new_task.add_tag(tag);
+        new_task.set_tags(tags);
+
+        if project_input == "-" {
+            new_task.clear_project();
+        } else if !project_input.is_empty() {
+            new_task.set_project(project_input);
         }
 
         if let Err(e) = new_task.set_priority(priority) {
@@ -191,9 +369,15 @@ pub fn edit_task(task_manager: &mut TaskManager, name: &str) {
             return;
         }
 
-        // Remove old task and add updated task
-        task_manager.remove_task(name);
-        task_manager.add_task(new_task);
+        if recurrence_input == "-" {
+            new_task.clear_recurrence();
+        } else if !recurrence_input.is_empty()
+            && let Err(e) = new_task.set_recurrence(recurrence_input) {
+            println!("Invalid repeat rule: {}", e);
+            return;
+        }
+
+        task_manager.update_task(new_task);
 
         println!("Task updated successfully.");
     } else {
@@ -202,30 +386,70 @@ pub fn edit_task(task_manager: &mut TaskManager, name: &str) {
 }
 
 
+/// Asks for a query until it parses, then lists the matching tasks.
+fn query_tasks(task_manager: &mut TaskManager) {
+    clear_console();
+    println!("Filter and sort with a query, for example:");
+    println!("   tag:work and priority>=7");
+    println!("   due<+3d and not status:done sort:due,-priority");
+    println!("   (tag:home or tag:errands) and status:pending");
+    println!("Fields: tag, priority, due, status, name, description, id, parent, created, updated, completed, is.");
+    let query = loop {
+        let input = read_input("\nEnter query (nothing to go back):");
+        if input.is_empty() {
+            return;
+        }
+        match Query::parse(&input) {
+            Ok(query) => break query,
+            Err(e) => println!("{}", e.explain(&input)),
+        }
+    };
+
+    clear_console();
+    let tasks = query.run(task_manager);
+    if tasks.is_empty() {
+        println!("No tasks match the query.");
+        wait();
+        return;
+    }
+    for (i, task) in tasks.iter().enumerate() {
+        print_task_line(task_manager, i + 1, 0, task);
+    }
+    println!("\n{} task(s) found.", tasks.len());
+
+    let ids: Vec<u32> = tasks.iter().map(|task| task.id()).collect();
+    let choice = read_input("\nEnter task number to view details");
+    if let Ok(index) = choice.parse::<usize>()
+        && index > 0
+        && let Some(&id) = ids.get(index - 1) {
+        task_actions(task_manager, id);
+    }
+}
+
 pub fn display_all_tasks(task_manager: &mut TaskManager) {
-    let tasks_with_due_date = task_manager.get_all_pending_tasks_with_due_date();
-    let tasks_without_due_date = task_manager.get_all_pending_tasks_without_due_date();
+    // Subtasks are listed under their parent instead of on their own
+    let tasks_with_due_date = task_manager.with_pending_subtasks(
+        task_manager.get_all_pending_tasks_with_due_date().into_iter()
+            .filter(|task| !task_manager.has_pending_parent(task))
+            .collect()
+    );
+    let tasks_without_due_date = task_manager.with_pending_subtasks(
+        task_manager.get_all_pending_tasks_without_due_date().into_iter()
+            .filter(|task| !task_manager.has_pending_parent(task))
+            .collect()
+    );
 
     if !tasks_with_due_date.is_empty() {
         println!("Tasks with due date:");
-        for (i, name) in tasks_with_due_date.iter().enumerate() {
-            print!("{}. ", i + 1 ,);
-            name.print_priority();
-            if name.due_date() < Some(chrono::Local::now().date_naive()) {
-                print!(" {}", name.due_date().unwrap().format("%Y-%m-%d").to_string().red());
-            } else {
-                print!(" {}", name.due_date().unwrap().format("%Y-%m-%d").to_string());
-            }
-            println!(" {} ", name.name())
+        for (i, (depth, task)) in tasks_with_due_date.iter().enumerate() {
+            print_task_line(task_manager, i + 1, *depth, task);
         }
     }
 
     if !tasks_without_due_date.is_empty() {
         println!("\nTasks without due date:");
-        for (i, name) in tasks_without_due_date.iter().enumerate() {
-            print!("{}. ", i + 1 + tasks_with_due_date.len());
-            name.print_priority();
-            println!(" {} ", name.name());
+        for (i, (depth, task)) in tasks_without_due_date.iter().enumerate() {
+            print_task_line(task_manager, i + 1 + tasks_with_due_date.len(), *depth, task);
         }
     }
 
@@ -237,9 +461,63 @@ pub fn display_all_tasks(task_manager: &mut TaskManager) {
         println!("\nTotal tasks: {}", task_manager.pending_task_count());
     }
 
-    let choice = read_input("\nEnter task number to view details or 'F' to filter tasks");
+    let ids: Vec<u32> = tasks_with_due_date.iter()
+        .chain(tasks_without_due_date.iter())
+        .map(|(_, task)| task.id())
+        .collect();
+
+    let choice = read_input("\nEnter task number to view details, 'F' to filter tasks by tag, 'S' to filter by status, 'Q' to query, 'M' to select several tasks or 'X' to delete all tasks");
     if !choice.is_empty() {
-        if choice.to_uppercase() == "F" {
+        if choice.to_uppercase() == "X" {
+            let confirm = read_input(&format!("Delete all {} task(s), including completed ones? (y/n)", task_manager.get_all_tasks().len()));
+            if confirm.to_lowercase() == "y" {
+                task_manager.clear_tasks();
+                println!("All tasks deleted. Undo from the main menu with 'U'.");
+            } else {
+                println!("No tasks deleted.");
+            }
+            wait();
+        } else if choice.to_uppercase() == "S" {
+            clear_console();
+            println!("Choose statuses to filter by (comma-separated):");
+            for status in Status::ALL {
+                println!("{} - {}", task_manager.list_tasks_with_status(&[status]).len(), status);
+            }
+            let mut statuses = Vec::new();
+            for part in read_input("Enter statuses:").split(',').filter(|part| !part.trim().is_empty()) {
+                match part.parse::<Status>() {
+                    Ok(status) => statuses.push(status),
+                    Err(e) => {
+                        println!("{}", e);
+                        wait();
+                        return;
+                    }
+                }
+            }
+            clear_console();
+            let filtered_tasks = task_manager.list_tasks_with_status(&statuses);
+            if filtered_tasks.is_empty() {
+                println!("No tasks found with the specified status.");
+                wait();
+                return;
+            }
+            println!("Filtered Tasks:");
+            for (i, task) in filtered_tasks.iter().enumerate() {
+                print_task_line(task_manager, i + 1, 0, task);
+            }
+
+            let filtered_ids: Vec<u32> = filtered_tasks.iter().map(|task| task.id()).collect();
+            let choice = read_input("\nEnter task number to view details");
+            if let Ok(index) = choice.parse::<usize>()
+                && index > 0
+                && let Some(&id) = filtered_ids.get(index - 1) {
+                task_actions(task_manager, id);
+            }
+        } else if choice.to_uppercase() == "Q" {
+            query_tasks(task_manager);
+        } else if choice.to_uppercase() == "M" {
+            bulk::display_bulk(task_manager, &ids);
+        } else if choice.to_uppercase() == "F" {
             clear_console();
             println!("Choose tags to filter by (comma-separated):");
             for tag in task_manager.get_all_tasks().iter()
@@ -264,16 +542,7 @@ pub fn display_all_tasks(task_manager: &mut TaskManager) {
             clear_console();
             println!("Filtered Tasks with date:");
             for (i, task) in filtered_tasks_with_due_date.iter().enumerate() {
-                print!("{}. ", i + 1);
-                task.print_priority();
-                if let Some(due_date) = task.due_date() {
-                    if due_date < chrono::Local::now().date_naive() {
-                        print!(" {}", due_date.format("%Y-%m-%d").to_string().red());
-                    } else {
-                        print!(" {}", due_date.format("%Y-%m-%d").to_string());
-                    }
-                }
-                println!(" {} ", task.name());
+                print_task_line(task_manager, i + 1, 0, task);
             }
             let filtered_tasks_without_due_date = if tags.is_empty() {
                 task_manager.get_all_pending_tasks_without_due_date()
@@ -285,9 +554,7 @@ pub fn display_all_tasks(task_manager: &mut TaskManager) {
             if !filtered_tasks_without_due_date.is_empty() {
                 println!("\nFiltered Tasks without date:");
                 for (i, task) in filtered_tasks_without_due_date.iter().enumerate() {
-                    print!("{}. ", i + 1 + filtered_tasks_with_due_date.len());
-                    task.print_priority();
-                    println!(" {} ", task.name());
+                    print_task_line(task_manager, i + 1 + filtered_tasks_with_due_date.len(), 0, task);
                 }
             }
 
@@ -297,69 +564,191 @@ pub fn display_all_tasks(task_manager: &mut TaskManager) {
                 return;
             }
 
+            let filtered_ids: Vec<u32> = filtered_tasks_with_due_date.iter()
+                .chain(filtered_tasks_without_due_date.iter())
+                .map(|task| task.id())
+                .collect();
+
             // Select task
             let choice = read_input("\nEnter task number to view details");
-            if let Ok(index) = choice.parse::<usize>() {
-                let selected_name = if index <= filtered_tasks_with_due_date.len() {
-                    filtered_tasks_with_due_date.get(index - 1).map(|task| task.name())
-                } else {
-                    filtered_tasks_without_due_date.get(index - 1 - filtered_tasks_with_due_date.len()).map(|task| task.name())
-                };
-                if let Some(task_name) = selected_name {
-                    if let Some(task) = task_manager.get_task(&task_name).cloned() {
-                        clear_console();
-                        display_task(&task);
-                        let action = read_input("\nActions: [E]dit, [C]omplete, [D]elete");
-                        match action.to_uppercase().as_str() {
-                            "E" => {
-                                edit_task(task_manager, &task.name());
-                            }
-                            "C" => {
-                                task_manager.mark_task_completed(&task.name());
-                                println!("Task marked as completed.");
-                            }
-                            "D" => {
-                                task_manager.remove_task(&task.name());
-                                println!("Task deleted successfully.");
-                            }
-                            _ => println!("Invalid action."),
-                        }
-                    }
-                }
+            if let Ok(index) = choice.parse::<usize>()
+                && index > 0
+                && let Some(&id) = filtered_ids.get(index - 1) {
+                task_actions(task_manager, id);
             }
         } else if let Ok(index) = choice.parse::<usize>() {
-            if index == 0 || index > task_manager.pending_task_count() {
+            if index == 0 || index > ids.len() {
                 println!("Invalid task number.");
                 wait();
                 return;
             }
-            let selected_name = if index <= tasks_with_due_date.len() {
-                tasks_with_due_date.get(index - 1).map(|task| task.name())
-            } else {
-                tasks_without_due_date.get(index - 1 - tasks_with_due_date.len()).map(|task| task.name())
-            };
-            if let Some(task_name) = selected_name {
-                if let Some(task) = task_manager.get_task(&task_name).cloned() {
-                    clear_console();
-                    display_task(&task);
-                    let action = read_input("\nActions: [E]dit, [C]omplete, [D]elete");
-                    match action.to_uppercase().as_str() {
-                        "E" => {
-                            edit_task(task_manager, &task.name());
-                        }
-                        "C" => {
-                            task_manager.mark_task_completed(&task.name());
-                            println!("Task marked as completed.");
-                        }
-                        "D" => {
-                            task_manager.remove_task(&task.name());
-                            println!("Task deleted successfully.");
+            task_actions(task_manager, ids[index - 1]);
+        }
+    }
+}
+
+pub fn print_task_line(task_manager: &TaskManager, number: usize, depth: usize, task: &Task) {
+    print!("{}. ", number);
+    if depth > 0 {
+        print!("{}└ ", "  ".repeat(depth - 1));
+    }
+    task.print_priority();
+    if let Some(due) = task.due_as_local_str() {
+        if task.is_overdue() {
+            print!(" {}", due.red());
+        } else {
+            print!(" {}", due);
+        }
+    }
+    let blocking_tasks = task_manager.blocking_tasks(task);
+    if blocking_tasks.is_empty() {
+        print!(" {}", task.name());
+    } else {
+        print!(" {}", task.name().dimmed());
+    }
+    match task.status() {
+        Status::InProgress => print!(" {}", "[in progress]".yellow()),
+        Status::Blocked => print!(" {}", "[blocked]".red()),
+        Status::Done => print!(" {}", "[done]".green()),
+        Status::Cancelled => print!(" {}", "[cancelled]".dimmed()),
+        Status::Todo => {}
+    }
+    if task.recurrence().is_some() {
+        print!(" {}", "↻".cyan());
+    }
+    if let Some(project) = task.project() {
+        print!(" {}", format!("@{}", project).cyan());
+    }
+    if let Some((done, total)) = task_manager.subtask_progress(task.id()) {
+        print!(" ({}/{} subtasks done)", done, total);
+    }
+    if !blocking_tasks.is_empty() {
+        let ids: Vec<String> = blocking_tasks.iter().map(|blocking| format!("#{}", blocking.id())).collect();
+        print!(" {}", format!("[blocked by {}]", ids.join(", ")).dimmed());
+    }
+    println!();
+}
+
+pub fn task_actions(task_manager: &mut TaskManager, id: u32) {
+    if let Some(task) = task_manager.get_task(id).cloned() {
+        clear_console();
+        display_task(task_manager, &task);
+        let action = read_input("\nActions: [E]dit, [C]omplete, [R]eopen, Change s[T]atus, [D]elete, Add [S]ubtask, Change [P]arent, Set dependencies ([B]locked by)");
+        match action.to_uppercase().as_str() {
+            "E" => {
+                edit_task(task_manager, task.id());
+            }
+            "C" => {
+                if task_manager.is_blocked(&task) {
+                    let confirm = read_input("This task is still blocked by its dependencies. Complete it anyway? (y/n)");
+                    if confirm.to_lowercase() != "y" {
+                        println!("Task not completed.");
+                        wait();
+                        return;
+                    }
+                }
+                match task_manager.mark_task_completed(task.id()) {
+                    Ok(change) => {
+                        println!("Task marked as completed.");
+                        print_status_change(task_manager, &change);
+                    }
+                    Err(e) => println!("Error: {}", e),
+                }
+            }
+            "R" => {
+                match task_manager.reopen_task(task.id()) {
+                    Ok(_) => println!("Task reopened."),
+                    Err(e) => println!("Error: {}", e),
+                }
+            }
+            "T" => {
+                let allowed: Vec<String> = task_manager.workflow().allowed_from(task.status()).iter()
+                    .map(|status| status.to_string())
+                    .collect();
+                if allowed.is_empty() {
+                    println!("The status of this task can't be changed.");
+                    wait();
+                    return;
+                }
+                let input = read_input(&format!("Enter new status ({}):", allowed.join(", ")));
+                match input.parse::<Status>().and_then(|status| task_manager.set_status(task.id(), status)) {
+                    Ok(change) => {
+                        println!("Status updated.");
+                        print_status_change(task_manager, &change);
+                    }
+                    Err(e) => println!("Error: {}", e),
+                }
+            }
+            "D" => {
+                let subtasks = task_manager.descendants_of(task.id()).len();
+                if subtasks > 0 {
+                    let confirm = read_input(&format!("This also deletes {} subtask(s). Continue? (y/n)", subtasks));
+                    if confirm.to_lowercase() != "y" {
+                        println!("Task not deleted.");
+                        wait();
+                        return;
+                    }
+                }
+                task_manager.remove_task(task.id());
+                println!("Task deleted successfully. Undo from the main menu with 'U'.");
+            }
+            "S" => {
+                clear_console();
+                println!("New subtask of: {}", task.name());
+                match read_task_details() {
+                    Ok(mut subtask) => {
+                        subtask.set_parent(Some(task.id()));
+                        task_manager.add_task(subtask);
+                        println!("Subtask added successfully.");
+                    }
+                    Err(e) => println!("Error: {}", e),
+                }
+            }
+            "P" => {
+                let input = read_input("Enter the ID of the new parent task (empty for top level):");
+                let parent = if input.is_empty() {
+                    Ok(None)
+                } else {
+                    input.parse::<u32>().map(Some).map_err(|_| "Invalid task ID.".to_string())
+                };
+                match parent.and_then(|parent| task_manager.set_parent(task.id(), parent)) {
+                    Ok(_) => println!("Task moved successfully."),
+                    Err(e) => println!("Error: {}", e),
+                }
+            }
+            "B" => {
+                let input = read_input("Enter the IDs of the tasks this one depends on (comma-separated, empty for none):");
+                let mut dependencies = Vec::new();
+                for part in input.split(',').map(|part| part.trim()).filter(|part| !part.is_empty()) {
+                    match part.parse::<u32>() {
+                        Ok(id) => dependencies.push(id),
+                        Err(_) => {
+                            println!("Invalid task ID: {}", part);
+                            wait();
+                            return;
                         }
-                        _ => println!("Invalid action."),
                     }
                 }
+                match task_manager.set_dependencies(task.id(), &dependencies) {
+                    Ok(()) => println!("Dependencies updated."),
+                    Err(e) => println!("Error: {} Dependencies not changed.", e),
+                }
             }
+            _ => println!("Invalid action."),
         }
+        wait();
+    }
+}
+
+fn print_status_change(task_manager: &TaskManager, change: &StatusChange) {
+    if let Some(next) = change.next_occurrence.and_then(|id| task_manager.get_task(id)) {
+        println!("Next occurrence due {}.", next.due_as_local_str().unwrap_or_default());
+    }
+    if !change.closed_subtasks.is_empty() {
+        println!("Also closed {} subtask(s).", change.closed_subtasks.len());
+    }
+    for unblocked in change.unblocked.iter().filter_map(|id| task_manager.get_task(*id)) {
+        println!("Unblocked: {}", unblocked.name());
     }
 }
 
@@ -370,21 +759,21 @@ pub fn wait() {
 pub fn print_tasks_for_today(task_manager: &TaskManager) {
     let today = chrono::Local::now().date_naive();
 
-    let tasks_before_today: Vec<&Task> = task_manager.get_all_pending_tasks_with_due_date()
+    let overdue_tasks: Vec<&Task> = task_manager.get_all_pending_tasks_with_due_date()
         .into_iter()
-        .filter(|task| task.due_date().map_or(false, |d| d < today))
+        .filter(|task| task.is_overdue())
         .collect();
 
     let tasks_for_today: Vec<&Task> = task_manager.get_all_pending_tasks_with_due_date()
         .into_iter()
-        .filter(|task| task.due_date().map_or(false, |d| d == today))
+        .filter(|task| !task.is_overdue() && task.due_date().is_some_and(|d| d == today))
         .collect();
 
     println!("Tasks for today:");
-    for task in tasks_before_today {
-        println!("- {} (Due: {})", task.name(), task.due_date().unwrap().format("%Y-%m-%d").to_string().red());
+    for task in overdue_tasks {
+        println!("- {} (Due: {})", task.name(), task.due_as_local_str().unwrap().red());
     }
     for task in tasks_for_today {
-        println!("- {} (Due: {})", task.name(), task.due_date().unwrap());
+        println!("- {} (Due: {})", task.name(), task.due_as_local_str().unwrap());
     }
 }
\ No newline at end of file
@@ -1,7 +1,10 @@
-use std::fs::{File, OpenOptions};
+use std::fs::File;
 use std::io;
 use std::path::Path;
-use crate::task::Task;
+use csv::StringRecord;
+use crate::history::{append_history_file, history_file, load_history_file, HistoryEntry};
+use crate::storage::{write_atomically, Storage};
+use crate::task::{format_timestamp, parse_timestamp, Task};
 
 pub struct CsvHandler {
     file_path: String,
@@ -13,73 +16,150 @@ impl CsvHandler {
             file_path
         }
     }
-    
-    pub fn load_tasks(&self) -> io::Result<Vec<Task>> {
+}
+
+impl Storage for CsvHandler {
+    fn load_tasks(&self) -> io::Result<Vec<Task>> {
         if !Path::new(&self.file_path).exists() {
             return Ok(Vec::new());
         }
         
         let file = File::open(&self.file_path)?;
         let mut csv_reader = csv::Reader::from_reader(file);
+        let headers = csv_reader.headers()?.clone();
         let mut tasks = Vec::new();
         
         for result in csv_reader.records() {
             let record = result?;
-            let mut task = Task::new(record[0].to_string());
-            
+            let field = |name: &str| field(&headers, &record, name);
+            let mut task = Task::new(field("name").to_string());
+
+            // ID (files written before IDs existed get them assigned by TaskManager)
+            if let Ok(id) = field("id").parse::<u32>() {
+                task.set_id(id);
+            }
             // Description
-            if !record[1].is_empty() {
-                task.set_description(record[1].to_string());
+            if !field("description").is_empty() {
+                task.set_description(field("description").to_string());
             }
             // Due date
-            if !record[2].is_empty() {
-                if let Err(e) = task.set_due_date(record[2].to_string()) {
-                    eprintln!("Error setting due date for task '{}': {}", task.name(), e);
-                }
+            if !field("due_date").is_empty()
+                && let Err(e) = task.set_due_date(field("due_date").to_string()) {
+                eprintln!("Error setting due date for task '{}': {}", task.name(), e);
             }
             // Tags
-            for tag in record[3].split(',') {
+            for tag in field("tags").split(',') {
                 let tag = tag.trim();
                 if !tag.is_empty() {
                     task.add_tag(tag.to_string());
                 }
             }
             // Priority
-            if let Ok(priority) = record[4].parse::<u8>() {
-                if let Err(e) = task.set_priority(priority) {
-                    eprintln!("Error setting priority for task '{}': {}", task.name(), e);
-                }
+            if let Ok(priority) = field("priority").parse::<u8>()
+                && let Err(e) = task.set_priority(priority) {
+                eprintln!("Error setting priority for task '{}': {}", task.name(), e);
             }
-            // Status
-            if record[5].parse::<bool>().unwrap_or(false) {
+            // Status (older files only have a completed column)
+            if !field("status").is_empty() {
+                match field("status").parse() {
+                    Ok(status) => task.set_status(status),
+                    Err(e) => eprintln!("Error setting status for task '{}': {}", task.name(), e),
+                }
+            } else if field("completed").parse::<bool>().unwrap_or(false) {
                 task.mark_completed();
             }
+            // Recurrence
+            if !field("recurrence").is_empty()
+                && let Err(e) = task.set_recurrence(field("recurrence").to_string()) {
+                eprintln!("Error setting repeat rule for task '{}': {}", task.name(), e);
+            }
+            // Parent
+            if let Ok(parent) = field("parent").parse::<u32>() {
+                task.set_parent(Some(parent));
+            }
+            // Dependencies
+            for dependency in field("dependencies").split(',') {
+                if let Ok(dependency) = dependency.trim().parse::<u32>() {
+                    task.add_dependency(dependency);
+                }
+            }
+            // Project
+            if !field("project").is_empty() {
+                task.set_project(field("project").to_string());
+            }
+            task.set_archived(field("archived").parse::<bool>().unwrap_or(false));
+            // Timestamps (missing in older files)
+            if !field("created_at").is_empty() {
+                match parse_timestamp(field("created_at")) {
+                    Ok(created_at) => task.set_created_at(created_at),
+                    Err(e) => eprintln!("Error setting creation time for task '{}': {}", task.name(), e),
+                }
+            }
+            if !field("updated_at").is_empty() {
+                match parse_timestamp(field("updated_at")) {
+                    Ok(updated_at) => task.set_updated_at(updated_at),
+                    Err(e) => eprintln!("Error setting modification time for task '{}': {}", task.name(), e),
+                }
+            }
+            if !field("completed_at").is_empty() {
+                match parse_timestamp(field("completed_at")) {
+                    Ok(completed_at) => task.set_completed_at(Some(completed_at)),
+                    Err(e) => eprintln!("Error setting completion time for task '{}': {}", task.name(), e),
+                }
+            }
             
             tasks.push(task);
         }
         Ok(tasks)
     }
     
-    pub fn save_tasks(&self, tasks: &[&Task]) -> io::Result<()> {
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&self.file_path)?;
+    fn save_tasks(&self, tasks: &[&Task]) -> io::Result<()> {
+        write_atomically(&self.file_path, |file| Self::write_tasks(file, tasks))
+    }
+
+    fn load_history(&self) -> io::Result<Vec<HistoryEntry>> {
+        load_history_file(&history_file(Path::new(&self.file_path)))
+    }
+
+    fn append_history(&self, entries: &[HistoryEntry]) -> io::Result<()> {
+        append_history_file(&history_file(Path::new(&self.file_path)), entries)
+    }
+}
+
+impl CsvHandler {
+    fn write_tasks(file: &mut File, tasks: &[&Task]) -> io::Result<()> {
         let mut csv_writer = csv::Writer::from_writer(file);
-        csv_writer.write_record(&[
-            "name", "description", "due_date", "tags", "priority", "completed"
+        csv_writer.write_record([
+            "id", "name", "description", "due_date", "tags", "priority", "status", "recurrence", "parent", "dependencies",
+            "project", "archived", "created_at", "updated_at", "completed_at"
         ])?;
         for task in tasks {
-            csv_writer.write_record(&[
+            csv_writer.write_record([
+                &task.id().to_string(),
                 task.name(),
                 task.description().unwrap_or(""),
                 &task.due_date_as_str().unwrap_or("".to_string()),
                 &task.tags_csv(),
                 &task.priority().to_string(),
-                &task.completed().to_string(),
+                &task.status().to_string(),
+                &task.recurrence_as_str().unwrap_or_default(),
+                &task.parent().map(|parent| parent.to_string()).unwrap_or_default(),
+                &task.dependencies_csv(),
+                task.project().unwrap_or(""),
+                &task.is_archived().to_string(),
+                &format_timestamp(task.created_at()),
+                &format_timestamp(task.updated_at()),
+                &task.completed_at().map(format_timestamp).unwrap_or_default(),
             ])?;
         }
-        Ok(())
+        csv_writer.flush()
     }
-}
\ No newline at end of file
+}
+
+/// Looks up a field by its header name, so older files with fewer columns still load.
+fn field<'a>(headers: &StringRecord, record: &'a StringRecord, name: &str) -> &'a str {
+    headers.iter()
+        .position(|header| header == name)
+        .and_then(|index| record.get(index))
+        .unwrap_or("")
+}
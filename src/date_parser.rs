@@ -0,0 +1,191 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+/// Resolves a relative or natural date against `today`. Understands `today`,
+/// `tomorrow`, `yesterday`, weekdays (`friday`, `this friday`, `next friday`
+/// for the one in the following week), `next week/month/year`, `in 3 days`,
+/// `+2w`, `end of week/month/year` and day/month dates like `24/12`.
+pub fn parse_relative_date(input: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let expression = input.trim().to_lowercase();
+    let words: Vec<&str> = expression.split_whitespace().collect();
+    match words.as_slice() {
+        ["today"] => Ok(today),
+        ["tomorrow"] => in_range(today.checked_add_days(Days::new(1))),
+        ["yesterday"] => in_range(today.checked_sub_days(Days::new(1))),
+        ["next", "week"] => in_range(today.checked_add_days(Days::new(7))),
+        ["next", "month"] => in_range(today.checked_add_months(Months::new(1))),
+        ["next", "year"] => in_range(today.checked_add_months(Months::new(12))),
+        ["end", "of", unit] | ["end", "of", "the", unit] => end_of(unit, today),
+        ["in", count, unit] => offset(count, unit, today),
+        ["next", day] if weekday(day).is_some() => {
+            // Same weekday in the week after this one (weeks start on Monday)
+            let days_ahead = 7 - today.weekday().num_days_from_monday() + weekday(day).unwrap().num_days_from_monday();
+            in_range(today.checked_add_days(Days::new(days_ahead as u64)))
+        }
+        [day] | ["this", day] if weekday(day).is_some() => {
+            let days_ahead = (7 + weekday(day).unwrap().num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+            in_range(today.checked_add_days(Days::new(days_ahead as u64)))
+        }
+        [shift] if shift.starts_with('+') => {
+            let shift = &shift[1..];
+            let split = shift.find(|c: char| !c.is_ascii_digit()).unwrap_or(shift.len());
+            offset(&shift[..split], &shift[split..], today)
+        }
+        [date] if date.contains(['/', '.']) => day_and_month(date, today),
+        ["next"] | ["this"] | ["in", _] => Err(format!("'{}' is incomplete. Say what comes next, e.g. 'next friday' or 'in 3 days'.", input.trim())),
+        [number] if number.parse::<u32>().is_ok() => {
+            Err(format!("'{}' is ambiguous. Use a full date like YYYY-MM-DD or an expression like 'in {} days'.", input.trim(), number))
+        }
+        _ => Err(format!(
+            "Unrecognized date '{}'. Use YYYY-MM-DD [HH:MM [+HH:MM]] or an expression like today, tomorrow, next friday, in 3 days, end of month or +2w.",
+            input.trim()
+        )),
+    }
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    word.parse().ok()
+}
+
+fn in_range(date: Option<NaiveDate>) -> Result<NaiveDate, String> {
+    date.ok_or_else(|| "Date out of range.".to_string())
+}
+
+fn offset(count: &str, unit: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let count: u32 = count.parse()
+        .map_err(|_| format!("'{}' is not a number.", count))?;
+    match unit {
+        "d" | "day" | "days" => in_range(today.checked_add_days(Days::new(count as u64))),
+        "w" | "week" | "weeks" => in_range(today.checked_add_days(Days::new(count as u64 * 7))),
+        "m" | "month" | "months" => in_range(today.checked_add_months(Months::new(count))),
+        "y" | "year" | "years" => in_range(count.checked_mul(12).and_then(|months| today.checked_add_months(Months::new(months)))),
+        "" => Err("Missing unit. Use days, weeks, months or years (d, w, m, y).".to_string()),
+        other => Err(format!("Unknown unit '{}'. Use days, weeks, months or years (d, w, m, y).", other)),
+    }
+}
+
+fn end_of(unit: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    match unit {
+        "week" => in_range(today.checked_add_days(Days::new(6 - today.weekday().num_days_from_monday() as u64))),
+        "month" => in_range(today.with_day(1).unwrap().checked_add_months(Months::new(1))
+            .and_then(|next_month| next_month.checked_sub_days(Days::new(1)))),
+        "year" => Ok(NaiveDate::from_ymd_opt(today.year(), 12, 31).unwrap()),
+        other => Err(format!("Unknown period '{}'. Use end of week, month or year.", other)),
+    }
+}
+
+/// `24/12`, `24.12.2025` and the like. Day and month are told apart by which
+/// one is over 12; dates like `3/4` are refused. Without a year the next such
+/// day is used.
+fn day_and_month(date: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let parts: Vec<u32> = date.split(['/', '.'])
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse::<u32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("Unrecognized date '{}'.", date))?;
+    let (first, second, year) = match parts.as_slice() {
+        [first, second] => (*first, *second, None),
+        [first, second, year] if *year >= 1000 => (*first, *second, Some(*year as i32)),
+        _ => return Err(format!("Unrecognized date '{}'. Use YYYY-MM-DD.", date)),
+    };
+    let (day, month) = if first > 12 || first == second {
+        (first, second)
+    } else if second > 12 {
+        (second, first)
+    } else {
+        return Err(format!("'{}' is ambiguous: it could be day/month or month/day. Use YYYY-MM-DD.", date));
+    };
+
+    let invalid = || format!("'{}' is not a valid date.", date);
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year, month, day).ok_or_else(invalid),
+        None => {
+            let this_year = NaiveDate::from_ymd_opt(today.year(), month, day);
+            match this_year {
+                Some(this_year) if this_year >= today => Ok(this_year),
+                _ => NaiveDate::from_ymd_opt(today.year() + 1, month, day).ok_or_else(invalid),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    // A Friday at the end of a long month
+    fn today() -> NaiveDate {
+        date(2025, 1, 31)
+    }
+
+    fn parse(input: &str) -> Result<NaiveDate, String> {
+        parse_relative_date(input, today())
+    }
+
+    #[test]
+    fn simple_expressions() {
+        assert_eq!(parse("today"), Ok(today()));
+        assert_eq!(parse(" Tomorrow "), Ok(date(2025, 2, 1)));
+        assert_eq!(parse("yesterday"), Ok(date(2025, 1, 30)));
+        assert_eq!(parse("next week"), Ok(date(2025, 2, 7)));
+        assert_eq!(parse("in 3 days"), Ok(date(2025, 2, 3)));
+        assert_eq!(parse("+2w"), Ok(date(2025, 2, 14)));
+        assert_eq!(parse("end of week"), Ok(date(2025, 2, 2)));
+        assert_eq!(parse("end of the year"), Ok(date(2025, 12, 31)));
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(parse("friday"), Ok(today()));
+        assert_eq!(parse("this monday"), Ok(date(2025, 2, 3)));
+        assert_eq!(parse("next friday"), Ok(date(2025, 2, 7)));
+        assert_eq!(parse("next monday"), Ok(date(2025, 2, 3)));
+    }
+
+    #[test]
+    fn months_clamp_to_the_end_of_short_months() {
+        assert_eq!(parse("next month"), Ok(date(2025, 2, 28)));
+        assert_eq!(parse("+1m"), Ok(date(2025, 2, 28)));
+        assert_eq!(parse_relative_date("+1y", date(2024, 2, 29)), Ok(date(2025, 2, 28)));
+        assert_eq!(parse("end of month"), Ok(today()));
+        assert_eq!(parse_relative_date("end of month", date(2024, 2, 10)), Ok(date(2024, 2, 29)));
+        assert_eq!(parse_relative_date("end of month", date(2025, 12, 5)), Ok(date(2025, 12, 31)));
+    }
+
+    #[test]
+    fn day_and_month_dates() {
+        assert_eq!(parse("24/12"), Ok(date(2025, 12, 24)));
+        assert_eq!(parse("12/24"), Ok(date(2025, 12, 24)));
+        assert_eq!(parse("15.1"), Ok(date(2026, 1, 15)));
+        assert_eq!(parse("24.12.2030"), Ok(date(2030, 12, 24)));
+        assert!(parse("3/4").unwrap_err().contains("ambiguous"));
+        assert_eq!(parse("31/13"), Err("'31/13' is not a valid date.".to_string()));
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        let error = Err("Date out of range.".to_string());
+        assert_eq!(parse("in 99999999 days"), error);
+        assert_eq!(parse("+4294967295w"), error);
+        assert_eq!(parse("+400000000m"), error);
+        assert_eq!(parse("+4294967295y"), error);
+        assert_eq!(parse_relative_date("tomorrow", NaiveDate::MAX), error);
+        assert_eq!(parse_relative_date("yesterday", NaiveDate::MIN), error);
+        assert_eq!(parse_relative_date("end of month", NaiveDate::MAX), error);
+        assert_eq!(parse("+99999999999999999999d"), Err("'99999999999999999999' is not a number.".to_string()));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse("next").unwrap_err().contains("incomplete"));
+        assert!(parse("in 3").unwrap_err().contains("incomplete"));
+        assert!(parse("12").unwrap_err().contains("ambiguous"));
+        assert!(parse("+3").unwrap_err().starts_with("Missing unit."));
+        assert!(parse("in 3 fortnights").unwrap_err().starts_with("Unknown unit 'fortnights'."));
+        assert!(parse("end of decade").unwrap_err().starts_with("Unknown period 'decade'."));
+        assert!(parse("someday").unwrap_err().starts_with("Unrecognized date 'someday'."));
+    }
+}
@@ -0,0 +1,114 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use crate::task::{format_timestamp, parse_timestamp, Task};
+
+/// A task field by name and how to show its value
+type FieldValue = (&'static str, fn(&Task) -> String);
+
+const COLUMNS: [&str; 6] = ["task_id", "field", "old_value", "new_value", "timestamp", "user"];
+
+/// One change to one field of a task. Creating and deleting a task are
+/// recorded with the fields `created` and `deleted`.
+#[derive(Clone)]
+pub struct HistoryEntry {
+    pub task_id: u32,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub timestamp: DateTime<Utc>,
+    pub user: String,
+}
+
+/// Who is making changes, from `$USER` (or `%USERNAME%` on Windows).
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// The fields that differ between two versions of a task, with their old and
+/// new values as text.
+pub fn field_changes(before: Option<&Task>, after: Option<&Task>) -> Vec<(&'static str, String, String)> {
+    let (before, after) = match (before, after) {
+        (None, Some(task)) => return vec![("created", String::new(), task.name().to_string())],
+        (Some(task), None) => return vec![("deleted", task.name().to_string(), String::new())],
+        (Some(before), Some(after)) => (before, after),
+        (None, None) => return Vec::new(),
+    };
+    let fields: [FieldValue; 11] = [
+        ("name", |task| task.name().to_string()),
+        ("description", |task| task.description().unwrap_or_default().to_string()),
+        ("due", |task| task.due_date_as_str().unwrap_or_default()),
+        ("tags", |task| task.tags_csv()),
+        ("priority", |task| task.priority().to_string()),
+        ("status", |task| task.status().to_string()),
+        ("repeat", |task| task.recurrence_as_str().unwrap_or_default()),
+        ("parent", |task| task.parent().map(|parent| parent.to_string()).unwrap_or_default()),
+        ("dependencies", |task| task.dependencies_csv()),
+        ("project", |task| task.project().unwrap_or_default().to_string()),
+        ("archived", |task| task.is_archived().to_string()),
+    ];
+    fields.iter()
+        .map(|(field, value)| (*field, value(before), value(after)))
+        .filter(|(_, old, new)| old != new)
+        .collect()
+}
+
+/// Where the history of a CSV or JSON data file is kept: `tasks.csv` has
+/// `tasks.csv.history.csv` next to it, so each data file has its own.
+pub fn history_file(data_file: &Path) -> PathBuf {
+    let mut name = data_file.as_os_str().to_owned();
+    name.push(".history.csv");
+    PathBuf::from(name)
+}
+
+pub fn load_history_file(path: &Path) -> io::Result<Vec<HistoryEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut reader = csv::Reader::from_reader(File::open(path)?);
+    let mut entries = Vec::new();
+    for result in reader.records() {
+        let record = result?;
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), message));
+        let task_id = record.get(0).unwrap_or_default().parse()
+            .map_err(|_| invalid(format!("invalid task ID '{}'", record.get(0).unwrap_or_default())))?;
+        let timestamp = parse_timestamp(record.get(4).unwrap_or_default()).map_err(invalid)?;
+        entries.push(HistoryEntry {
+            task_id,
+            field: record.get(1).unwrap_or_default().to_string(),
+            old_value: record.get(2).unwrap_or_default().to_string(),
+            new_value: record.get(3).unwrap_or_default().to_string(),
+            timestamp,
+            user: record.get(5).unwrap_or_default().to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Adds entries to the end of a history file, creating it with a header if needed.
+pub fn append_history_file(path: &Path, entries: &[HistoryEntry]) -> io::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let is_new = file.metadata()?.len() == 0;
+    let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(file);
+    if is_new {
+        writer.write_record(COLUMNS)?;
+    }
+    for entry in entries {
+        writer.write_record([
+            entry.task_id.to_string(),
+            entry.field.clone(),
+            entry.old_value.clone(),
+            entry.new_value.clone(),
+            format_timestamp(entry.timestamp),
+            entry.user.clone(),
+        ])?;
+    }
+    writer.flush()?;
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()
+}
@@ -0,0 +1,126 @@
+use serde::Serialize;
+use crate::task::{format_timestamp, Task};
+use crate::task_manager::TaskManager;
+
+/// Serializable view of a task, including derived fields.
+#[derive(Serialize)]
+pub struct TaskJson<'a> {
+    id: u32,
+    name: &'a str,
+    description: Option<&'a str>,
+    due_date: Option<String>,
+    tags: Vec<&'a str>,
+    priority: u8,
+    status: String,
+    completed: bool,
+    recurrence: Option<String>,
+    parent: Option<u32>,
+    dependencies: Vec<u32>,
+    project: Option<&'a str>,
+    archived: bool,
+    overdue: bool,
+    due_today: bool,
+    blocked: bool,
+    subtasks_done: usize,
+    subtasks_total: usize,
+    created_at: String,
+    updated_at: String,
+    completed_at: Option<String>,
+}
+
+impl<'a> TaskJson<'a> {
+    pub fn new(task_manager: &TaskManager, task: &'a Task) -> Self {
+        let mut tags: Vec<&str> = task.tags().iter().map(|tag| tag.as_str()).collect();
+        tags.sort();
+        let (subtasks_done, subtasks_total) = task_manager.subtask_progress(task.id()).unwrap_or((0, 0));
+        TaskJson {
+            id: task.id(),
+            name: task.name(),
+            description: task.description(),
+            due_date: task.due_date_as_str(),
+            tags,
+            priority: task.priority(),
+            status: task.status().to_string(),
+            completed: task.completed(),
+            recurrence: task.recurrence_as_str(),
+            parent: task.parent(),
+            dependencies: task.dependencies().iter().copied().collect(),
+            project: task.project(),
+            archived: task.is_archived(),
+            overdue: task.is_overdue(),
+            due_today: task.is_due_today(),
+            blocked: task_manager.is_blocked(task),
+            subtasks_done,
+            subtasks_total,
+            created_at: format_timestamp(task.created_at()),
+            updated_at: format_timestamp(task.updated_at()),
+            completed_at: task.completed_at().map(format_timestamp),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReportJson<'a> {
+    pub pending: usize,
+    pub completed: usize,
+    pub overdue: Vec<TaskJson<'a>>,
+    pub due_today: Vec<TaskJson<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct ProjectJson<'a> {
+    pub name: &'a str,
+    pub pending: usize,
+    pub completed: usize,
+    pub archived: bool,
+}
+
+#[derive(Serialize)]
+pub struct AgendaJson<'a> {
+    pub overdue: Vec<TaskJson<'a>>,
+    pub days: Vec<AgendaDayJson<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct AgendaDayJson<'a> {
+    pub date: String,
+    pub tasks: Vec<TaskJson<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct BoardColumnJson<'a> {
+    pub title: String,
+    pub tasks: Vec<TaskJson<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct CalendarDayJson {
+    pub date: String,
+    pub due: usize,
+    pub overdue: bool,
+}
+
+#[derive(Serialize)]
+pub struct SearchResultJson<'a> {
+    pub score: u32,
+    pub task: TaskJson<'a>,
+}
+
+#[derive(Serialize)]
+pub struct ViewJson<'a> {
+    pub name: &'a str,
+    pub filter: &'a str,
+    pub sort: &'a str,
+    pub columns: Vec<&'static str>,
+}
+
+pub fn tasks_to_json<'a>(task_manager: &TaskManager, tasks: &[&'a Task]) -> Vec<TaskJson<'a>> {
+    tasks.iter().map(|task| TaskJson::new(task_manager, task)).collect()
+}
+
+pub fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Error serializing to JSON: {}", e),
+    }
+}
@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::history::{append_history_file, history_file, load_history_file, HistoryEntry};
+use crate::storage::{write_atomically, Storage};
+use crate::task::{format_timestamp, parse_timestamp, Task};
+
+/// Version of the file layout written by this build. Files with a newer
+/// version are refused rather than silently losing fields.
+/// 1: initial format
+/// 2: recurrence
+/// 3: parent
+/// 4: dependencies
+/// 5: status instead of completed
+/// 6: created, updated and completed timestamps
+/// 7: due dates with a time and UTC offset
+/// 8: project and archived
+const FORMAT_VERSION: u32 = 8;
+
+#[derive(Serialize, Deserialize)]
+struct TaskFile {
+    version: u32,
+    tasks: Vec<TaskRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TaskRecord {
+    id: u32,
+    name: String,
+    description: Option<String>,
+    due_date: Option<String>,
+    tags: Vec<String>,
+    priority: u8,
+    #[serde(default)]
+    status: Option<String>,
+    /// Only read from files older than version 5
+    #[serde(default, skip_serializing)]
+    completed: bool,
+    #[serde(default)]
+    recurrence: Option<String>,
+    #[serde(default)]
+    parent: Option<u32>,
+    #[serde(default)]
+    dependencies: Vec<u32>,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default)]
+    archived: bool,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    updated_at: Option<String>,
+    #[serde(default)]
+    completed_at: Option<String>,
+}
+
+impl From<&Task> for TaskRecord {
+    fn from(task: &Task) -> Self {
+        let mut tags: Vec<String> = task.tags().iter().cloned().collect();
+        tags.sort();
+        TaskRecord {
+            id: task.id(),
+            name: task.name().to_string(),
+            description: task.description().map(|description| description.to_string()),
+            due_date: task.due_date_as_str(),
+            tags,
+            priority: task.priority(),
+            status: Some(task.status().to_string()),
+            completed: false,
+            recurrence: task.recurrence_as_str(),
+            parent: task.parent(),
+            dependencies: task.dependencies().iter().copied().collect(),
+            project: task.project().map(|project| project.to_string()),
+            archived: task.is_archived(),
+            created_at: Some(format_timestamp(task.created_at())),
+            updated_at: Some(format_timestamp(task.updated_at())),
+            completed_at: task.completed_at().map(format_timestamp),
+        }
+    }
+}
+
+impl TaskRecord {
+    fn into_task(self) -> io::Result<Task> {
+        let mut task = Task::new(self.name);
+        task.set_id(self.id);
+        if let Some(description) = self.description {
+            task.set_description(description);
+        }
+        if let Some(due_date) = self.due_date {
+            task.set_due_date(due_date).map_err(invalid_data)?;
+        }
+        for tag in self.tags {
+            task.add_tag(tag);
+        }
+        task.set_priority(self.priority).map_err(invalid_data)?;
+        if let Some(status) = self.status {
+            task.set_status(status.parse().map_err(invalid_data)?);
+        } else if self.completed {
+            task.mark_completed();
+        }
+        if let Some(recurrence) = self.recurrence {
+            task.set_recurrence(recurrence).map_err(invalid_data)?;
+        }
+        task.set_parent(self.parent);
+        for dependency in self.dependencies {
+            task.add_dependency(dependency);
+        }
+        if let Some(project) = self.project {
+            task.set_project(project);
+        }
+        task.set_archived(self.archived);
+        if let Some(created_at) = self.created_at {
+            task.set_created_at(parse_timestamp(&created_at).map_err(invalid_data)?);
+        }
+        if let Some(updated_at) = self.updated_at {
+            task.set_updated_at(parse_timestamp(&updated_at).map_err(invalid_data)?);
+        }
+        if let Some(completed_at) = self.completed_at {
+            task.set_completed_at(Some(parse_timestamp(&completed_at).map_err(invalid_data)?));
+        }
+        Ok(task)
+    }
+}
+
+pub struct JsonStorage {
+    file_path: String,
+}
+
+impl JsonStorage {
+    pub fn new(file_path: String) -> JsonStorage {
+        JsonStorage {
+            file_path
+        }
+    }
+}
+
+impl Storage for JsonStorage {
+    fn load_tasks(&self) -> io::Result<Vec<Task>> {
+        if !Path::new(&self.file_path).exists() {
+            return Ok(Vec::new());
+        }
+
+        let file = File::open(&self.file_path)?;
+        let task_file: TaskFile = serde_json::from_reader(BufReader::new(file))?;
+        if task_file.version > FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "{} uses format version {}, but this version only supports up to {}",
+                self.file_path, task_file.version, FORMAT_VERSION
+            )));
+        }
+        task_file.tasks.into_iter().map(TaskRecord::into_task).collect()
+    }
+
+    fn save_tasks(&self, tasks: &[&Task]) -> io::Result<()> {
+        write_atomically(&self.file_path, |file| {
+            let task_file = TaskFile {
+                version: FORMAT_VERSION,
+                tasks: tasks.iter().map(|task| TaskRecord::from(*task)).collect(),
+            };
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer_pretty(&mut writer, &task_file)?;
+            writer.write_all(b"\n")?;
+            writer.flush()
+        })
+    }
+
+    fn load_history(&self) -> io::Result<Vec<HistoryEntry>> {
+        load_history_file(&history_file(Path::new(&self.file_path)))
+    }
+
+    fn append_history(&self, entries: &[HistoryEntry]) -> io::Result<()> {
+        append_history_file(&history_file(Path::new(&self.file_path)), entries)
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -1,41 +1,146 @@
 use crate::task_manager::TaskManager;
 use std::process::exit;
+use clap::Parser;
 use colored::Colorize;
+use crate::cli::{Cli, Command, EXIT_FAILURE};
+use crate::config::{Config, CONFIG_FILE};
+use crate::storage::{persist_changes, save_all, Storage, StorageKind};
+use crate::backup::BackedUpStorage;
 use crate::console::{clear_console, read_input, wait, print_tasks_for_today, display_all_tasks, display_completed_tasks, read_task_details};
 
 pub mod task;
+mod recurrence;
 mod csv_handler;
 mod task_manager;
 mod console;
+mod cli;
+mod json_output;
+mod storage;
+mod config;
+mod sqlite_storage;
+mod json_storage;
+mod backup;
+mod signals;
+mod status;
+mod date_parser;
+mod tui;
+mod calendar;
+mod board;
+mod query;
+mod views;
+mod search;
+mod history;
+mod bulk;
+mod projects;
 
 
 
 fn main() {
+    let mut cli = Cli::parse();
+    let start_tui = matches!(cli.command, Some(Command::Tui));
+    if start_tui {
+        cli.command = None;
+    }
+    let mut config = match Config::load(CONFIG_FILE) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error in config file: {}", e);
+            exit(EXIT_FAILURE);
+        }
+    };
+    if let Some(kind) = cli.storage {
+        // A file configured for another backend would be in the wrong format
+        if cli.file.is_none() && config.storage != kind {
+            config.file = None;
+        }
+        config.storage = kind;
+    }
+    if let Some(file) = cli.file.clone() {
+        if cli.storage.is_none()
+            && let Some(kind) = StorageKind::from_path(&file) {
+            config.storage = kind;
+        }
+        config.file = Some(file);
+    }
+    let storage = match BackedUpStorage::open(config.storage, config.data_file(), config.backups) {
+        Ok(storage) => storage,
+        Err(e) => {
+            eprintln!("Error opening storage: {}", e);
+            exit(EXIT_FAILURE);
+        }
+    };
     let mut task_manager = TaskManager::new();
-    let csv_handler = csv_handler::CsvHandler::new("tasks.csv".to_string());
+    task_manager.set_workflow(config.workflow.clone());
+
+    // New changes are still recorded if the old history can't be read. It is loaded
+    // first so tasks without an ID don't get one that was used before
+    match storage.load_history() {
+        Ok(history) => task_manager.load_history(history),
+        Err(e) => eprintln!("Warning: cannot read the change history: {}", e),
+    }
 
     // Loading tasks
-    if let Ok(tasks) = csv_handler.load_tasks() {
-        for task in tasks {
-            task_manager.add_task(task);
+    match storage.load_tasks() {
+        Ok(tasks) => task_manager.load_tasks(tasks),
+        Err(e) if cli.command.is_some() => {
+            // Never overwrite a file we failed to read when running non-interactively
+            eprintln!("Error loading tasks from file: {}", e);
+            exit(EXIT_FAILURE);
+        }
+        Err(_) => {
+            println!("Error loading tasks from file. Starting with an empty task list.");
+            wait();
         }
-    } else {
-        println!("Error loading tasks from file. Starting with an empty task list.");
-        wait();
+    }
+
+    if let Some(command) = cli.command {
+        exit(cli::run(command, cli.json, &mut task_manager, &storage, &config));
+    }
+
+    match signals::save_on_interrupt(config.storage, config.data_file(), config.backups) {
+        Ok(unsaved_tasks) => task_manager.on_change(move |task_manager| unsaved_tasks.update(task_manager)),
+        Err(e) => eprintln!("Warning: changes will not be saved on Ctrl+C: {}", e),
+    }
+
+    if start_tui {
+        if let Err(e) = tui::run(&mut task_manager, &storage, config.autosave) {
+            eprintln!("Error running the full-screen interface: {}", e);
+        }
+        if let Err(e) = save_all(&mut task_manager, &storage) {
+            eprintln!("Error saving tasks: {}", e);
+            exit(EXIT_FAILURE);
+        }
+        exit(0);
     }
 
     loop {
         clear_console();
-        println!("Task Manager");
+        if task_manager.has_unsaved_changes() {
+            println!("Task Manager {}", "(unsaved changes)".yellow());
+        } else {
+            println!("Task Manager");
+        }
         print_tasks_for_today(&task_manager);
         println!("(1) List and manage tasks");
         println!("(2) Add a new task");
         println!("(3) View completed tasks");
         println!("(4) Exit and save tasks");
+        println!("(5) Full-screen mode");
+        println!("(6) Agenda and calendar");
+        println!("(7) Board");
+        println!("(8) Saved views");
+        println!("(9) Search");
+        println!("(P) Projects");
+        if let Some(label) = task_manager.undo_label() {
+            println!("(U) Undo: {}", label);
+        }
+        if let Some(label) = task_manager.redo_label() {
+            println!("(R) Redo: {}", label);
+        }
         
         let choice = read_input("Choose an option:");
 
-        match choice.as_str() {
+        match choice.to_uppercase().as_str() {
             "1" => {
                 clear_console();
                 display_all_tasks(&mut task_manager);
@@ -44,8 +149,8 @@ fn main() {
                 clear_console();
                 match read_task_details() {
                     Ok(task) => {
-                        if task_manager.get_task(&task.name()).is_some() {
-                            let confirm = read_input("Task with this name already exists. Do you want to overwrite it? (y/n)");
+                        if task_manager.find_task_by_name(task.name()).is_some() {
+                            let confirm = read_input("Task with this name already exists. Do you want to add it anyway? (y/n)");
                             if confirm.to_lowercase() != "y" {
                                 println!("Task not added.");
                                 wait();
@@ -64,20 +169,65 @@ fn main() {
             }
             "3" => {
                 clear_console();
-                display_completed_tasks(&task_manager);
+                display_completed_tasks(&mut task_manager);
             }
             "4" => {
                 clear_console();
-                if let Err(e) = csv_handler.save_tasks(&task_manager.get_all_tasks()) {
+                if let Err(e) = save_all(&mut task_manager, &storage) {
+                    // Stay open so the changes aren't lost
                     println!("Error saving tasks: {}", e);
-                } else {
-                    println!("Tasks saved successfully.");
+                    wait();
+                    continue;
                 }
+                println!("Tasks saved successfully.");
                 exit(0);
             }
+            "5" => {
+                if let Err(e) = tui::run(&mut task_manager, &storage, config.autosave) {
+                    println!("Error running the full-screen interface: {}", e);
+                    wait();
+                }
+            }
+            "6" => {
+                clear_console();
+                calendar::display_calendar(&mut task_manager);
+            }
+            "7" => {
+                clear_console();
+                board::display_board(&mut task_manager);
+            }
+            "8" => {
+                clear_console();
+                views::display_views(&mut task_manager, &config.data_file());
+            }
+            "9" => {
+                clear_console();
+                search::display_search(&mut task_manager);
+            }
+            "P" => {
+                projects::display_projects(&mut task_manager);
+            }
+            "U" => {
+                if let Some(label) = task_manager.undo() {
+                    println!("Undone: {}", label);
+                    wait();
+                }
+            }
+            "R" => {
+                if let Some(label) = task_manager.redo() {
+                    println!("Redone: {}", label);
+                    wait();
+                }
+            }
             _ => {
                 //
             }
         }
+
+        if (config.autosave || storage.updates_incrementally())
+            && let Err(e) = persist_changes(&mut task_manager, &storage) {
+            println!("Error saving tasks: {}", e);
+            wait();
+        }
     }
 }
@@ -0,0 +1,66 @@
+use colored::Colorize;
+use crate::console::{clear_console, print_task_line, read_input, task_actions, wait};
+use crate::task_manager::TaskManager;
+
+/// Lists projects with their task counts until the user goes back.
+pub fn display_projects(task_manager: &mut TaskManager) {
+    loop {
+        clear_console();
+        let projects: Vec<String> = task_manager.projects().into_iter().map(|project| project.to_string()).collect();
+        if projects.is_empty() {
+            println!("No projects yet. Give a task a project when adding or editing it.");
+            wait();
+            return;
+        }
+        println!("Projects:");
+        for (i, project) in projects.iter().enumerate() {
+            print!("{}. {} ({} pending, {} completed)", i + 1, project.bold(),
+                task_manager.project_pending_count(project), task_manager.project_completed_count(project));
+            if task_manager.is_project_archived(project) {
+                print!(" {}", "[archived]".dimmed());
+            }
+            println!();
+        }
+
+        let choice = read_input("\nEnter a project number to open it, or nothing to go back");
+        match choice.parse::<usize>() {
+            Ok(number) if number > 0 && number <= projects.len() => display_project(task_manager, &projects[number - 1]),
+            _ => return,
+        }
+    }
+}
+
+/// Lists every task in a project, archived or not, until the user goes back.
+fn display_project(task_manager: &mut TaskManager, project: &str) {
+    loop {
+        clear_console();
+        let tasks = task_manager.tasks_in_project(project);
+        if tasks.is_empty() {
+            return;
+        }
+        let archived = task_manager.is_project_archived(project);
+        println!("{}{}\n", project.bold(), if archived { " (archived)" } else { "" });
+        for (i, task) in tasks.iter().enumerate() {
+            print_task_line(task_manager, i + 1, 0, task);
+        }
+        println!("\n{} pending, {} completed",
+            task_manager.project_pending_count(project), task_manager.project_completed_count(project));
+        let ids: Vec<u32> = tasks.iter().map(|task| task.id()).collect();
+
+        let action = if archived { "'A' to unarchive the project" } else { "'A' to archive the project" };
+        let choice = read_input(&format!("\nEnter task number to manage it, {}, or nothing to go back", action));
+        if choice.to_uppercase() == "A" {
+            match task_manager.set_project_archived(project, !archived) {
+                Ok(count) if archived => println!("Unarchived {} task(s).", count),
+                Ok(count) => println!("Archived {} task(s). They are hidden from the task lists until the project is unarchived.", count),
+                Err(e) => println!("{}", e),
+            }
+            wait();
+            continue;
+        }
+        match choice.parse::<usize>() {
+            Ok(index) if index > 0 && index <= ids.len() => task_actions(task_manager, ids[index - 1]),
+            _ => return,
+        }
+    }
+}
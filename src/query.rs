@@ -0,0 +1,636 @@
+use std::cmp::Ordering;
+use std::fmt;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use crate::date_parser::parse_relative_date;
+use crate::status::Status;
+use crate::task::Task;
+use crate::task_manager::TaskManager;
+
+/// A filter and sort order over tasks, written like
+/// `tag:work and priority>=7 and due<+3d and not status:done sort:due,-priority`.
+///
+/// Conditions are `field op value` with the operators `:` (or `=`), `!=`, `<`,
+/// `<=`, `>` and `>=`. They combine with `and` (also implied between terms),
+/// `or`, `not` and parentheses. A bare word matches the name or description,
+/// as does a word like `re:port` that doesn't start with a field name.
+/// Dates may be relative (`today`, `+3d`, `"next friday"`) and `none` matches
+/// tasks without a value. `is:` checks `overdue`, `today`, `blocked`,
+/// `recurring`, `subtask` or `archived`. Tasks in archived projects are left
+/// out unless the query mentions `is:archived`.
+pub struct Query {
+    filter: Option<Expr>,
+    sort: Vec<(Field, bool)>,
+}
+
+#[derive(Debug)]
+pub struct QueryError {
+    pub message: String,
+    /// Character offset in the query where the problem is
+    pub position: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at position {})", self.message, self.position + 1)
+    }
+}
+
+impl QueryError {
+    /// The query with a caret under the problem, followed by the message.
+    pub fn explain(&self, query: &str) -> String {
+        format!("{}\n{}^\n{}", query, " ".repeat(self.position), self.message)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Field {
+    Tag,
+    Priority,
+    Due,
+    Status,
+    Name,
+    Description,
+    Id,
+    Parent,
+    Created,
+    Updated,
+    Completed,
+    Project,
+    /// Flags derived from other fields, like `is:overdue`
+    Is,
+}
+
+/// Values accepted by `is:`
+const FLAGS: [&str; 6] = ["overdue", "today", "blocked", "recurring", "subtask", "archived"];
+
+impl Field {
+    const ALL: [(&'static str, Field); 13] = [
+        ("tag", Field::Tag),
+        ("priority", Field::Priority),
+        ("due", Field::Due),
+        ("status", Field::Status),
+        ("name", Field::Name),
+        ("description", Field::Description),
+        ("id", Field::Id),
+        ("parent", Field::Parent),
+        ("created", Field::Created),
+        ("updated", Field::Updated),
+        ("completed", Field::Completed),
+        ("project", Field::Project),
+        ("is", Field::Is),
+    ];
+
+    fn named(name: &str) -> Option<Field> {
+        let name = name.to_lowercase();
+        Field::ALL.iter().find(|(field_name, _)| *field_name == name).map(|(_, field)| *field)
+    }
+
+    fn from_name(name: &str, position: usize) -> Result<Field, QueryError> {
+        if let Some(field) = Field::named(name) {
+            return Ok(field);
+        }
+        let name = name.to_lowercase();
+        let names: Vec<&str> = Field::ALL.iter().map(|(field_name, _)| *field_name).collect();
+        let message = match names.iter().find(|field_name| !name.is_empty() && field_name.starts_with(&name)) {
+            Some(suggestion) => format!("Unknown field '{}'. Did you mean '{}'?", name, suggestion),
+            None => format!("Unknown field '{}'. Use one of: {}.", name, names.join(", ")),
+        };
+        Err(QueryError { message, position })
+    }
+
+    fn name(&self) -> &'static str {
+        Field::ALL.iter().find(|(_, field)| field == self).unwrap().0
+    }
+
+    fn is_date(&self) -> bool {
+        matches!(self, Field::Due | Field::Created | Field::Updated | Field::Completed)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Value {
+    Text(String),
+    Number(u32),
+    Date(NaiveDate),
+    Status(Status),
+    Pending,
+    Closed,
+    None,
+}
+
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Condition(Field, Op, Value),
+    Text(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Open,
+    Close,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        let mut tokens = Vec::new();
+        let mut sort = Vec::new();
+        for (token, position) in tokenize(input)? {
+            match &token {
+                Token::Word(word) if word.to_lowercase().starts_with("sort:") => {
+                    sort = parse_sort(&word[5..], position + 5)?;
+                }
+                _ => tokens.push((token, position)),
+            }
+        }
+
+        let mut parser = Parser { tokens, next: 0, end: input.chars().count() };
+        let filter = if parser.tokens.is_empty() { None } else { Some(parser.or()?) };
+        if let Some((token, position)) = parser.tokens.get(parser.next) {
+            let message = match token {
+                Token::Close => "Unmatched ')'.".to_string(),
+                _ => "Unexpected text.".to_string(),
+            };
+            return Err(QueryError { message, position: *position });
+        }
+        Ok(Query { filter, sort })
+    }
+
+    /// Splits a valid query into the text of its filter and the keys of its
+    /// `sort:` clause, e.g. `due,-priority`.
+    pub fn split_sort(input: &str) -> Result<(String, String), QueryError> {
+        Query::parse(input)?;
+        let tokens = tokenize(input)?;
+        let chars: Vec<char> = input.chars().collect();
+        let mut filter = String::new();
+        let mut sort = String::new();
+        let mut kept_from = 0;
+        for (i, (token, position)) in tokens.iter().enumerate() {
+            if let Token::Word(word) = token
+                && word.to_lowercase().starts_with("sort:") {
+                filter.extend(&chars[kept_from..*position]);
+                // Up to where the next token starts, as quotes are gone from the word
+                kept_from = tokens.get(i + 1).map_or(chars.len(), |(_, next)| *next);
+                sort = word[5..].to_string();
+            }
+        }
+        filter.extend(&chars[kept_from..]);
+        Ok((filter.trim().to_string(), sort))
+    }
+
+    pub fn matches(&self, task_manager: &TaskManager, task: &Task) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter.matches(task_manager, task))
+    }
+
+    /// Tasks matching the filter, in the requested order (by ID otherwise).
+    pub fn run<'a>(&self, task_manager: &'a TaskManager) -> Vec<&'a Task> {
+        let archived = self.filter.as_ref().is_some_and(|filter| filter.mentions_archived());
+        let mut tasks: Vec<&Task> = task_manager.get_all_tasks().into_iter()
+            .filter(|task| archived || !task.is_archived())
+            .filter(|task| self.matches(task_manager, task))
+            .collect();
+        self.sort(&mut tasks);
+        tasks
+    }
+
+    pub fn sort(&self, tasks: &mut [&Task]) {
+        tasks.sort_by(|a, b| {
+            self.sort.iter()
+                .map(|(field, descending)| {
+                    let ordering = compare_by(*field, a, b);
+                    if *descending { ordering.reverse() } else { ordering }
+                })
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+}
+
+/// Orders two tasks by a field. Tasks without a value come last.
+fn compare_by(field: Field, a: &Task, b: &Task) -> Ordering {
+    fn missing_last<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+    match field {
+        Field::Tag => a.tags_csv().cmp(&b.tags_csv()),
+        Field::Priority => a.priority().cmp(&b.priority()),
+        Field::Due => missing_last(a.deadline(), b.deadline()),
+        Field::Status => Status::ALL.iter().position(|s| *s == a.status())
+            .cmp(&Status::ALL.iter().position(|s| *s == b.status())),
+        Field::Name => a.name().to_lowercase().cmp(&b.name().to_lowercase()),
+        Field::Description => missing_last(a.description(), b.description()),
+        Field::Id => a.id().cmp(&b.id()),
+        Field::Parent => missing_last(a.parent(), b.parent()),
+        Field::Created => a.created_at().cmp(&b.created_at()),
+        Field::Updated => a.updated_at().cmp(&b.updated_at()),
+        Field::Completed => missing_last(a.completed_at(), b.completed_at()),
+        Field::Project => missing_last(a.project().map(str::to_lowercase), b.project().map(str::to_lowercase)),
+        Field::Is => unreachable!(),
+    }
+}
+
+fn parse_sort(keys: &str, position: usize) -> Result<Vec<(Field, bool)>, QueryError> {
+    let mut sort = Vec::new();
+    let mut offset = position;
+    for key in keys.split(',') {
+        let (name, descending) = match key.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (key, false),
+        };
+        if name.is_empty() {
+            return Err(QueryError { message: "Expected a field to sort by.".to_string(), position: offset });
+        }
+        let field = Field::from_name(name, offset)?;
+        if field == Field::Is {
+            return Err(QueryError { message: "Can't sort by 'is'.".to_string(), position: offset });
+        }
+        sort.push((field, descending));
+        offset += key.chars().count() + 1;
+    }
+    Ok(sort)
+}
+
+/// Splits a query into words and parentheses, keeping quoted text together.
+/// Each token comes with its character position.
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push((Token::Open, position));
+            }
+            ')' => {
+                chars.next();
+                tokens.push((Token::Close, position));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    if c == '"' {
+                        // Quoted text, e.g. due<"next friday"
+                        loop {
+                            match chars.next() {
+                                Some((_, '"')) => break,
+                                Some((_, c)) => word.push(c),
+                                None => return Err(QueryError { message: "Missing closing quote.".to_string(), position }),
+                            }
+                        }
+                    } else {
+                        word.push(c);
+                    }
+                }
+                tokens.push((Token::Word(word), position));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+    /// Position reported for errors at the end of the query
+    end: usize,
+}
+
+impl Parser {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.next), Some((Token::Word(word), _)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.and()?;
+        while self.peek_keyword("or") {
+            self.next += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.not()?;
+        loop {
+            if self.peek_keyword("and") {
+                self.next += 1;
+            } else if self.peek_keyword("or") || matches!(self.tokens.get(self.next), None | Some((Token::Close, _))) {
+                return Ok(expr);
+            }
+            // Terms next to each other must all match
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr, QueryError> {
+        if self.peek_keyword("not") {
+            self.next += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        let Some((token, position)) = self.tokens.get(self.next) else {
+            return Err(QueryError { message: "Expected a condition here.".to_string(), position: self.end });
+        };
+        let position = *position;
+        self.next += 1;
+        match token {
+            Token::Open => {
+                let expr = self.or()?;
+                match self.tokens.get(self.next) {
+                    Some((Token::Close, _)) => {
+                        self.next += 1;
+                        Ok(expr)
+                    }
+                    _ => Err(QueryError { message: "Missing ')' to close this '('.".to_string(), position }),
+                }
+            }
+            Token::Close => Err(QueryError { message: "Expected a condition before ')'.".to_string(), position }),
+            Token::Word(word) if ["and", "or"].contains(&word.to_lowercase().as_str()) => {
+                Err(QueryError { message: format!("Expected a condition before '{}'.", word), position })
+            }
+            Token::Word(word) => condition(word, position),
+        }
+    }
+}
+
+/// Parses a single `field op value` term, or a bare word to search for.
+fn condition(word: &str, position: usize) -> Result<Expr, QueryError> {
+    // Words like `re:port` that don't start with a field name are searched for
+    let Some((op_start, field)) = word.find([':', '=', '!', '<', '>'])
+        .and_then(|op_start| Some((op_start, Field::named(&word[..op_start])?))) else {
+        return Ok(Expr::Text(word.to_lowercase()));
+    };
+    // Positions count characters, not bytes
+    let op_position = position + word[..op_start].chars().count();
+    let rest = &word[op_start..];
+    let (op, op_length) = [("!=", Op::Ne), ("<=", Op::Le), (">=", Op::Ge), (":", Op::Eq), ("=", Op::Eq), ("<", Op::Lt), (">", Op::Gt)]
+        .iter()
+        .find(|(symbol, _)| rest.starts_with(symbol))
+        .map(|(symbol, op)| (*op, symbol.len()))
+        .ok_or_else(|| QueryError { message: "Expected an operator like ':', '!=', '<' or '>='.".to_string(), position: op_position })?;
+    let value_position = position + word[..op_start + op_length].chars().count();
+    let raw = &word[op_start + op_length..];
+    if raw.is_empty() {
+        return Err(QueryError { message: format!("Expected a value after '{}'.", &word[..op_start + op_length]), position: value_position });
+    }
+
+    let error = |message: String| QueryError { message, position: value_position };
+    let ordered = !matches!(op, Op::Eq | Op::Ne);
+    let value = if raw.eq_ignore_ascii_case("none") && !matches!(field, Field::Priority | Field::Id | Field::Status | Field::Name | Field::Is) {
+        Value::None
+    } else {
+        match field {
+            Field::Priority | Field::Id | Field::Parent => Value::Number(raw.parse()
+                .map_err(|_| error(format!("{} must be a number.", field.name())))?),
+            Field::Due | Field::Created | Field::Updated | Field::Completed => {
+                let today = Local::now().date_naive();
+                let date = NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                    .or_else(|_| parse_relative_date(raw, today))
+                    .map_err(error)?;
+                Value::Date(date)
+            }
+            Field::Status => match raw.to_lowercase().as_str() {
+                "pending" | "open" => Value::Pending,
+                "closed" => Value::Closed,
+                _ => Value::Status(raw.parse().map_err(error)?),
+            },
+            Field::Tag | Field::Name | Field::Description | Field::Project => Value::Text(raw.to_lowercase()),
+            Field::Is if FLAGS.contains(&raw.to_lowercase().as_str()) => Value::Text(raw.to_lowercase()),
+            Field::Is => return Err(error(format!("Unknown flag '{}'. Use one of: {}.", raw, FLAGS.join(", ")))),
+        }
+    };
+    if ordered && (matches!(value, Value::None) || !(field.is_date() || matches!(field, Field::Priority | Field::Id))) {
+        return Err(QueryError {
+            message: format!("'{}' can only compare numbers and dates; use ':' or '!=' with {}.", &rest[..op_length], field.name()),
+            position: op_position,
+        });
+    }
+    Ok(Expr::Condition(field, op, value))
+}
+
+impl Expr {
+    fn matches(&self, task_manager: &TaskManager, task: &Task) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(task_manager, task) && right.matches(task_manager, task),
+            Expr::Or(left, right) => left.matches(task_manager, task) || right.matches(task_manager, task),
+            Expr::Not(expr) => !expr.matches(task_manager, task),
+            Expr::Text(text) => {
+                task.name().to_lowercase().contains(text)
+                    || task.description().is_some_and(|description| description.to_lowercase().contains(text))
+            }
+            Expr::Condition(field, op, value) => condition_matches(task_manager, *field, *op, value, task),
+        }
+    }
+
+    fn mentions_archived(&self) -> bool {
+        match self {
+            Expr::And(left, right) | Expr::Or(left, right) => left.mentions_archived() || right.mentions_archived(),
+            Expr::Not(expr) => expr.mentions_archived(),
+            Expr::Text(_) => false,
+            Expr::Condition(field, _, value) => *field == Field::Is && matches!(value, Value::Text(flag) if flag == "archived"),
+        }
+    }
+}
+
+fn condition_matches(task_manager: &TaskManager, field: Field, op: Op, value: &Value, task: &Task) -> bool {
+    let local_date = |timestamp: DateTime<Utc>| timestamp.with_timezone(&Local).date_naive();
+    let date = match field {
+        Field::Due => Some(task.due_date()),
+        Field::Created => Some(Some(local_date(task.created_at()))),
+        Field::Updated => Some(Some(local_date(task.updated_at()))),
+        Field::Completed => Some(task.completed_at().map(local_date)),
+        _ => None,
+    };
+
+    match (value, date) {
+        (Value::None, Some(date)) => (op == Op::Eq) == date.is_none(),
+        (Value::Date(value), Some(date)) => date.is_some_and(|date| op.holds(date.cmp(value))),
+        (Value::None, None) => match field {
+            Field::Tag => (op == Op::Eq) == task.tags().is_empty(),
+            Field::Description => (op == Op::Eq) == task.description().is_none(),
+            Field::Parent => (op == Op::Eq) == task.parent().is_none(),
+            Field::Project => (op == Op::Eq) == task.project().is_none(),
+            _ => false,
+        },
+        (Value::Number(value), _) => match field {
+            Field::Priority => op.holds((task.priority() as u32).cmp(value)),
+            Field::Id => op.holds(task.id().cmp(value)),
+            Field::Parent => (op == Op::Eq) == (task.parent() == Some(*value)),
+            _ => false,
+        },
+        (Value::Text(value), _) => {
+            let found = match field {
+                Field::Tag => task.tags().iter().any(|tag| tag.to_lowercase() == *value),
+                Field::Name => task.name().to_lowercase().contains(value),
+                Field::Description => task.description().is_some_and(|description| description.to_lowercase().contains(value)),
+                Field::Project => task.project().is_some_and(|project| project.to_lowercase() == *value),
+                Field::Is => match value.as_str() {
+                    "overdue" => task.is_overdue(),
+                    "today" => task.is_due_today(),
+                    "blocked" => task_manager.is_blocked(task),
+                    "recurring" => task.recurrence().is_some(),
+                    "archived" => task.is_archived(),
+                    _ => task.parent().is_some(),
+                },
+                _ => false,
+            };
+            (op == Op::Eq) == found
+        }
+        (Value::Status(status), _) => (op == Op::Eq) == (task.status() == *status),
+        (Value::Pending, _) => (op == Op::Eq) == task.is_pending(),
+        (Value::Closed, _) => (op == Op::Eq) != task.is_pending(),
+        (Value::Date(_), None) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged(tags: &[&str], priority: u8) -> Task {
+        let mut task = Task::new(tags.join(" "));
+        for tag in tags {
+            task.add_tag(tag.to_string());
+        }
+        task.set_priority(priority).unwrap();
+        task
+    }
+
+    fn matches(query: &str, task: &Task) -> bool {
+        Query::parse(query).unwrap().matches(&TaskManager::new(), task)
+    }
+
+    fn error(query: &str) -> (String, usize) {
+        let error = Query::parse(query).err().unwrap();
+        (error.message, error.position)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let a = tagged(&["a"], 5);
+        let b = tagged(&["b"], 5);
+        let b_and_c = tagged(&["b", "c"], 5);
+        assert!(matches("tag:a or tag:b and tag:c", &a));
+        assert!(!matches("tag:a or tag:b and tag:c", &b));
+        assert!(matches("tag:a or tag:b and tag:c", &b_and_c));
+        assert!(!matches("(tag:a or tag:b) and tag:c", &a));
+        // Terms next to each other are joined with `and`
+        assert!(!matches("tag:a or tag:b tag:c", &b));
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        let b = tagged(&["b"], 5);
+        let a_and_b = tagged(&["a", "b"], 5);
+        assert!(matches("not tag:a and tag:b", &b));
+        assert!(!matches("not tag:a and tag:b", &a_and_b));
+        assert!(matches("not (tag:a and tag:c) and tag:b", &a_and_b));
+        assert!(matches("not not tag:b", &b));
+    }
+
+    #[test]
+    fn comparisons_and_none() {
+        let task = tagged(&["work"], 7);
+        assert!(matches("priority>=7 and priority<8", &task));
+        assert!(!matches("priority>7", &task));
+        assert!(matches("due:none project:none", &task));
+        assert!(!matches("tag:none", &task));
+        assert!(matches("TAG:Work", &task));
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_problem() {
+        assert_eq!(error("sort:prio"), ("Unknown field 'prio'. Did you mean 'priority'?".to_string(), 5));
+        assert_eq!(error("tag:a and priority>"), ("Expected a value after 'priority>'.".to_string(), 19));
+        assert_eq!(error("tag:a)"), ("Unmatched ')'.".to_string(), 5));
+        assert_eq!(error("(tag:a"), ("Missing ')' to close this '('.".to_string(), 0));
+        assert_eq!(error("tag:a or"), ("Expected a condition here.".to_string(), 8));
+        assert_eq!(error("tag<a"), ("'<' can only compare numbers and dates; use ':' or '!=' with tag.".to_string(), 3));
+        assert_eq!(error("priority:high"), ("priority must be a number.".to_string(), 9));
+        assert_eq!(error("is:late").0, format!("Unknown flag 'late'. Use one of: {}.", FLAGS.join(", ")));
+        assert_eq!(error("name:\"open").0, "Missing closing quote.");
+        assert_eq!(error("sort:is").0, "Can't sort by 'is'.");
+    }
+
+    #[test]
+    fn positions_count_characters() {
+        let query = "name:café and tag<a";
+        let error = Query::parse(query).err().unwrap();
+        assert_eq!(error.position, 17);
+        assert_eq!(error.explain(query).lines().nth(1), Some("                 ^"));
+    }
+
+    #[test]
+    fn words_without_a_field_are_searched_for() {
+        let mut task = Task::new("Check re:port numbers".to_string());
+        task.set_description("a<b".to_string());
+        assert!(matches("re:port", &task));
+        assert!(matches("a<b", &task));
+        assert!(!matches("re:ports", &task));
+        assert!(matches("prio:5 or tag:none", &task));
+    }
+
+    #[test]
+    fn dates_out_of_range_are_errors() {
+        assert_eq!(error("due<\"in 99999999 days\""), ("Date out of range.".to_string(), 4));
+        assert_eq!(error("due>+400000000m").0, "Date out of range.");
+    }
+
+    #[test]
+    fn sort_clause_is_split_off_by_token() {
+        let split = |query: &str| Query::split_sort(query).unwrap();
+        assert_eq!(split("tag:a sort:due"), ("tag:a".to_string(), "due".to_string()));
+        assert_eq!(split("(tag:a sort:due,-priority)"), ("(tag:a )".to_string(), "due,-priority".to_string()));
+        assert_eq!(split("name:\"a sort:b\" or tag:x"), ("name:\"a sort:b\" or tag:x".to_string(), String::new()));
+        assert_eq!(split("sort:id due<\"next friday\""), ("due<\"next friday\"".to_string(), "id".to_string()));
+        assert!(Query::split_sort("(tag:a sort:due").is_err());
+    }
+
+    #[test]
+    fn sort_keys() {
+        let low = tagged(&["low"], 2);
+        let high = tagged(&["high"], 9);
+        let mut tasks = vec![&low, &high];
+        Query::parse("sort:-priority").unwrap().sort(&mut tasks);
+        assert_eq!(tasks[0].name(), "high");
+        Query::parse("sort:priority").unwrap().sort(&mut tasks);
+        assert_eq!(tasks[0].name(), "low");
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+use std::str::FromStr;
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+/// Longest interval accepted by `every:N`, about ten years
+pub const MAX_EVERY_DAYS: u32 = 3660;
+
+/// How often a task repeats. Written as `daily`, `weekly`, `weekly:mon,fri`,
+/// `monthly`, `monthly:15`, `every:3` (days) or `yearly`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Recurrence {
+    Daily,
+    /// On the given weekdays, or on the due date's weekday if empty
+    Weekly(Vec<Weekday>),
+    /// On the given day of the month, or the due date's day if none. Days past
+    /// the end of a short month fall on its last day.
+    Monthly(Option<u32>),
+    EveryNDays(u32),
+    Yearly,
+}
+
+impl Recurrence {
+    /// Returns the first occurrence strictly after `date`, or an error if it
+    /// is past the last date that can be represented.
+    pub fn next_after(&self, date: NaiveDate) -> Result<NaiveDate, String> {
+        let next = match self {
+            Recurrence::Daily => date.checked_add_days(Days::new(1)),
+            Recurrence::Weekly(days) if days.is_empty() => date.checked_add_days(Days::new(7)),
+            Recurrence::Weekly(days) => {
+                (1..=7)
+                    .filter_map(|offset| date.checked_add_days(Days::new(offset)))
+                    .find(|candidate| days.contains(&candidate.weekday()))
+            }
+            Recurrence::Monthly(day) => {
+                let day = day.unwrap_or(date.day());
+                let this_month = day_in_month(date.year(), date.month(), day);
+                if this_month > date {
+                    Some(this_month)
+                } else {
+                    date.with_day(1).unwrap().checked_add_months(Months::new(1))
+                        .map(|next| day_in_month(next.year(), next.month(), day))
+                }
+            }
+            Recurrence::EveryNDays(days) => date.checked_add_days(Days::new(*days as u64)),
+            Recurrence::Yearly => date.checked_add_months(Months::new(12)),
+        };
+        next.ok_or_else(|| "The next occurrence is out of range.".to_string())
+    }
+}
+
+/// The given day of a month, clamped to the month's last day.
+fn day_in_month(year: i32, month: u32, day: u32) -> NaiveDate {
+    (1..=day.min(31))
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .unwrap()
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim().to_lowercase();
+        let (kind, argument) = match input.split_once(':') {
+            Some((kind, argument)) => (kind.trim(), Some(argument.trim())),
+            None => (input.as_str(), None),
+        };
+
+        match (kind, argument) {
+            ("daily", None) => Ok(Recurrence::Daily),
+            ("weekly", None) => Ok(Recurrence::Weekly(Vec::new())),
+            ("weekly", Some(days)) => {
+                let mut weekdays = Vec::new();
+                for day in days.split(',') {
+                    let weekday = day.trim().parse::<Weekday>()
+                        .map_err(|_| format!("Unknown weekday '{}'.", day.trim()))?;
+                    if !weekdays.contains(&weekday) {
+                        weekdays.push(weekday);
+                    }
+                }
+                weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+                Ok(Recurrence::Weekly(weekdays))
+            }
+            ("monthly", None) => Ok(Recurrence::Monthly(None)),
+            ("monthly", Some(day)) => match day.parse::<u32>() {
+                Ok(day) if (1..=31).contains(&day) => Ok(Recurrence::Monthly(Some(day))),
+                _ => Err("Day of month must be between 1 and 31.".to_string()),
+            },
+            ("every", Some(days)) => match days.trim_end_matches('d').parse::<u32>() {
+                Ok(days) if (1..=MAX_EVERY_DAYS).contains(&days) => Ok(Recurrence::EveryNDays(days)),
+                _ => Err(format!("Use every:N with N a number of days from 1 to {}.", MAX_EVERY_DAYS)),
+            },
+            ("yearly", None) => Ok(Recurrence::Yearly),
+            _ => Err("Invalid repeat rule. Use daily, weekly[:mon,fri], monthly[:15], every:N or yearly.".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(days) if days.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly(days) => {
+                let days: Vec<String> = days.iter()
+                    .map(|day| day.to_string().to_lowercase())
+                    .collect();
+                write!(f, "weekly:{}", days.join(","))
+            }
+            Recurrence::Monthly(None) => write!(f, "monthly"),
+            Recurrence::Monthly(Some(day)) => write!(f, "monthly:{}", day),
+            Recurrence::EveryNDays(days) => write!(f, "every:{}", days),
+            Recurrence::Yearly => write!(f, "yearly"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn monthly_clamps_to_the_end_of_short_months() {
+        let rule: Recurrence = "monthly:31".parse().unwrap();
+        assert_eq!(rule.next_after(date(2025, 1, 31)), Ok(date(2025, 2, 28)));
+        assert_eq!(rule.next_after(date(2024, 1, 31)), Ok(date(2024, 2, 29)));
+        assert_eq!(rule.next_after(date(2025, 2, 28)), Ok(date(2025, 3, 31)));
+        assert_eq!(Recurrence::Monthly(None).next_after(date(2025, 1, 31)), Ok(date(2025, 2, 28)));
+        assert_eq!(Recurrence::Monthly(Some(15)).next_after(date(2025, 1, 10)), Ok(date(2025, 1, 15)));
+    }
+
+    #[test]
+    fn yearly_from_a_leap_day_falls_on_the_end_of_february() {
+        assert_eq!(Recurrence::Yearly.next_after(date(2024, 2, 29)), Ok(date(2025, 2, 28)));
+    }
+
+    #[test]
+    fn weekly_finds_the_next_listed_weekday() {
+        let rule: Recurrence = "weekly:fri,mon".parse().unwrap();
+        assert_eq!(rule, Recurrence::Weekly(vec![Weekday::Mon, Weekday::Fri]));
+        // 2025-01-31 is a Friday
+        assert_eq!(rule.next_after(date(2025, 1, 31)), Ok(date(2025, 2, 3)));
+        assert_eq!(Recurrence::Weekly(Vec::new()).next_after(date(2025, 1, 31)), Ok(date(2025, 2, 7)));
+    }
+
+    #[test]
+    fn every_is_bounded() {
+        assert_eq!("every:3".parse(), Ok(Recurrence::EveryNDays(3)));
+        assert_eq!("every:3660d".parse(), Ok(Recurrence::EveryNDays(MAX_EVERY_DAYS)));
+        assert!("every:0".parse::<Recurrence>().is_err());
+        assert!("every:3661".parse::<Recurrence>().is_err());
+        assert!("every:4000000000".parse::<Recurrence>().is_err());
+        assert!("every:99999999999999999999".parse::<Recurrence>().is_err());
+    }
+
+    #[test]
+    fn next_occurrence_past_the_last_date_is_an_error() {
+        let error = Err("The next occurrence is out of range.".to_string());
+        assert_eq!(Recurrence::Daily.next_after(NaiveDate::MAX), error);
+        assert_eq!(Recurrence::Weekly(vec![Weekday::Mon]).next_after(NaiveDate::MAX), error);
+        assert_eq!(Recurrence::Monthly(Some(1)).next_after(NaiveDate::MAX), error);
+        assert_eq!(Recurrence::Yearly.next_after(NaiveDate::MAX), error);
+        assert_eq!(Recurrence::EveryNDays(MAX_EVERY_DAYS).next_after(NaiveDate::MAX - Days::new(10)), error);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("monthly:32".parse::<Recurrence>(), Err("Day of month must be between 1 and 31.".to_string()));
+        assert_eq!("weekly:mon,funday".parse::<Recurrence>(), Err("Unknown weekday 'funday'.".to_string()));
+        assert!("hourly".parse::<Recurrence>().is_err());
+        assert!("daily:2".parse::<Recurrence>().is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for rule in ["daily", "weekly", "weekly:mon,fri", "monthly", "monthly:15", "every:3", "yearly"] {
+            assert_eq!(rule.parse::<Recurrence>().unwrap().to_string(), rule);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::BTreeSet;
+use colored::Colorize;
+use crate::console::{clear_console, read_input, status_marker, task_actions, wait};
+use crate::task::Task;
+use crate::task_manager::TaskManager;
+
+/// How much a match in each field counts
+const NAME_WEIGHT: u32 = 3;
+const TAG_WEIGHT: u32 = 2;
+const DESCRIPTION_WEIGHT: u32 = 1;
+
+/// Characters of description shown around the first match
+const SNIPPET_WIDTH: usize = 60;
+
+/// A task found by a search, with the characters that matched in each field.
+pub struct SearchResult<'a> {
+    pub task: &'a Task,
+    pub score: u32,
+    /// Positions (in characters) of matched text in the name
+    pub name_matches: BTreeSet<usize>,
+    pub description_matches: BTreeSet<usize>,
+    /// Matched tags, sorted, with the positions that matched in each
+    pub tag_matches: Vec<(String, BTreeSet<usize>)>,
+}
+
+/// Finds tasks whose name, description or tags match every word of the
+/// search. Words match exactly, as a prefix or substring, with a typo or two
+/// in longer words, or as letters in order (`rprt` finds "report"). Results
+/// are ranked best first, pending tasks before closed ones on a tie. Tasks in
+/// archived projects are left out, as in the task lists.
+pub fn search<'a>(task_manager: &'a TaskManager, input: &str) -> Vec<SearchResult<'a>> {
+    let terms: Vec<Vec<char>> = input.split_whitespace().map(lowercase_chars).collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let mut results: Vec<SearchResult> = task_manager.get_all_tasks().into_iter()
+        .filter(|task| !task.is_archived())
+        .filter_map(|task| match_task(task, &terms))
+        .collect();
+    results.sort_by(|a, b| {
+        b.score.cmp(&a.score)
+            .then(b.task.is_pending().cmp(&a.task.is_pending()))
+            .then(a.task.id().cmp(&b.task.id()))
+    });
+    results
+}
+
+fn match_task<'a>(task: &'a Task, terms: &[Vec<char>]) -> Option<SearchResult<'a>> {
+    let name = lowercase_chars(task.name());
+    let description = lowercase_chars(task.description().unwrap_or_default());
+    let mut tags: Vec<&String> = task.tags().iter().collect();
+    tags.sort();
+
+    let mut result = SearchResult {
+        task,
+        score: 0,
+        name_matches: BTreeSet::new(),
+        description_matches: BTreeSet::new(),
+        tag_matches: Vec::new(),
+    };
+    for term in terms {
+        let mut best = 0;
+        if let Some((score, positions)) = match_text(&name, term) {
+            best = best.max(score * NAME_WEIGHT);
+            result.name_matches.extend(positions);
+        }
+        if let Some((score, positions)) = match_text(&description, term) {
+            best = best.max(score * DESCRIPTION_WEIGHT);
+            result.description_matches.extend(positions);
+        }
+        for tag in &tags {
+            if let Some((score, positions)) = match_text(&lowercase_chars(tag), term) {
+                best = best.max(score * TAG_WEIGHT);
+                match result.tag_matches.iter_mut().find(|(matched, _)| matched == *tag) {
+                    Some((_, matched_positions)) => matched_positions.extend(positions),
+                    None => result.tag_matches.push((tag.to_string(), positions.into_iter().collect())),
+                }
+            }
+        }
+        // Every word has to match somewhere
+        if best == 0 {
+            return None;
+        }
+        result.score += best;
+    }
+    Some(result)
+}
+
+fn lowercase_chars(text: &str) -> Vec<char> {
+    text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect()
+}
+
+/// Scores how well a search word matches a text and returns the matched
+/// positions, or `None` when it doesn't match at all.
+fn match_text(text: &[char], term: &[char]) -> Option<(u32, Vec<usize>)> {
+    if text.is_empty() {
+        return None;
+    }
+    let is_word_start = |i: usize| i == 0 || !text[i - 1].is_alphanumeric();
+    let is_word_end = |i: usize| i == text.len() || !text[i].is_alphanumeric();
+
+    // Substrings, preferring whole words and word starts
+    let mut best: Option<(u32, usize)> = None;
+    for start in 0..text.len().saturating_sub(term.len() - 1) {
+        if text[start..].starts_with(term) {
+            let score = match (is_word_start(start), is_word_end(start + term.len())) {
+                (true, true) => 100,
+                (true, false) => 80,
+                _ => 60,
+            };
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, start));
+            }
+        }
+    }
+    if let Some((score, start)) = best {
+        return Some((score, (start..start + term.len()).collect()));
+    }
+
+    // Words within a small edit distance
+    if term.len() >= 4 {
+        let allowed = if term.len() >= 8 { 2 } else { 1 };
+        let mut start = 0;
+        while start < text.len() {
+            let end = (start..text.len()).find(|&i| !text[i].is_alphanumeric()).unwrap_or(text.len());
+            let distance = edit_distance(&text[start..end], term);
+            if end > start && distance <= allowed {
+                return Some((40 - distance as u32 * 10, (start..end).collect()));
+            }
+            start = end + 1;
+        }
+    }
+
+    // Letters in order, close together
+    let mut positions = Vec::new();
+    let mut next = 0;
+    for c in term {
+        let found = (next..text.len()).find(|&i| text[i] == *c)?;
+        positions.push(found);
+        next = found + 1;
+    }
+    let span = positions.last().unwrap() - positions[0] + 1;
+    if term.len() < 2 || span > term.len() * 3 {
+        return None;
+    }
+    Some(((20 * term.len() / span) as u32, positions))
+}
+
+/// Levenshtein distance, counting a swap of neighbouring letters as one edit.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            rows[i][j] = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                rows[i][j] = rows[i][j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Text with the matched characters highlighted.
+pub fn highlight(text: &str, matches: &BTreeSet<usize>) -> String {
+    let mut highlighted = String::new();
+    let mut run = String::new();
+    for (i, c) in text.chars().enumerate() {
+        if matches.contains(&i) {
+            run.push(c);
+            continue;
+        }
+        if !run.is_empty() {
+            highlighted.push_str(&run.yellow().bold().underline().to_string());
+            run.clear();
+        }
+        highlighted.push(c);
+    }
+    if !run.is_empty() {
+        highlighted.push_str(&run.yellow().bold().underline().to_string());
+    }
+    highlighted
+}
+
+/// The part of the description around its first match, highlighted.
+fn description_snippet(description: &str, matches: &BTreeSet<usize>) -> String {
+    let length = description.chars().count();
+    let first = matches.first().copied().unwrap_or(0);
+    let start = first.saturating_sub(SNIPPET_WIDTH / 3).min(length.saturating_sub(SNIPPET_WIDTH));
+    let end = (start + SNIPPET_WIDTH).min(length);
+    let text: String = description.chars().skip(start).take(end - start).collect();
+    let shifted = matches.iter().filter(|&&i| i >= start && i < end).map(|i| i - start).collect();
+    format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        highlight(&text, &shifted),
+        if end < length { "…" } else { "" }
+    )
+}
+
+/// Prints one result with its matches highlighted: name, tags and, if the
+/// description matched, the part of it around the match.
+pub fn print_result(number: Option<usize>, result: &SearchResult) {
+    let task = result.task;
+    match number {
+        Some(number) => print!("{}. ", number),
+        None => print!("{:>4} ", task.id()),
+    }
+    print!("{} {}", status_marker(task.status()), highlight(task.name(), &result.name_matches));
+    let mut tags: Vec<&String> = task.tags().iter().collect();
+    tags.sort();
+    for tag in tags {
+        match result.tag_matches.iter().find(|(matched, _)| matched == tag) {
+            Some((_, positions)) => print!(" #{}", highlight(tag, positions)),
+            None => print!(" {}", format!("#{}", tag).dimmed()),
+        }
+    }
+    println!();
+    if let Some(description) = task.description()
+        && !result.description_matches.is_empty() {
+        println!("      {}", description_snippet(description, &result.description_matches));
+    }
+}
+
+pub fn display_search(task_manager: &mut TaskManager) {
+    let input = read_input("Search for:");
+    if input.trim().is_empty() {
+        return;
+    }
+    loop {
+        clear_console();
+        let results = search(task_manager, &input);
+        if results.is_empty() {
+            println!("No tasks match '{}'.", input);
+            wait();
+            return;
+        }
+        println!("{} task(s) match '{}':\n", results.len(), input);
+        for (i, result) in results.iter().enumerate() {
+            print_result(Some(i + 1), result);
+        }
+        let ids: Vec<u32> = results.iter().map(|result| result.task.id()).collect();
+
+        let choice = read_input("\nEnter task number to manage it, or nothing to go back");
+        match choice.parse::<usize>() {
+            Ok(index) if index > 0 && index <= ids.len() => task_actions(task_manager, ids[index - 1]),
+            _ => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_manager() -> TaskManager {
+        let mut task_manager = TaskManager::new();
+        task_manager.add_task(Task::new("Write the quarterly report".to_string()));
+        let mut review = Task::new("Review code".to_string());
+        review.set_description("Check the report generator".to_string());
+        task_manager.add_task(review);
+        let mut milk = Task::new("Buy milk".to_string());
+        milk.add_tag("errands".to_string());
+        task_manager.add_task(milk);
+        task_manager
+    }
+
+    fn names(task_manager: &TaskManager, input: &str) -> Vec<String> {
+        search(task_manager, input).iter().map(|result| result.task.name().to_string()).collect()
+    }
+
+    #[test]
+    fn names_rank_above_descriptions() {
+        let task_manager = task_manager();
+        assert_eq!(names(&task_manager, "report"), ["Write the quarterly report", "Review code"]);
+    }
+
+    #[test]
+    fn every_word_has_to_match() {
+        let task_manager = task_manager();
+        assert_eq!(names(&task_manager, "buy errands"), ["Buy milk"]);
+        assert!(names(&task_manager, "buy report").is_empty());
+        assert!(names(&task_manager, "   ").is_empty());
+    }
+
+    #[test]
+    fn typos_and_abbreviations() {
+        let task_manager = task_manager();
+        assert_eq!(names(&task_manager, "reprot")[0], "Write the quarterly report");
+        assert_eq!(names(&task_manager, "quartrely")[0], "Write the quarterly report");
+        assert_eq!(names(&task_manager, "rvw"), ["Review code"]);
+        assert_eq!(names(&task_manager, "mulk"), ["Buy milk"]);
+        // Words under four letters get no typos
+        assert!(names(&task_manager, "bay").is_empty());
+        assert!(names(&task_manager, "xyz").is_empty());
+    }
+
+    #[test]
+    fn archived_projects_are_left_out() {
+        let mut task_manager = task_manager();
+        let mut old = Task::new("Old report".to_string());
+        old.set_project("Archive".to_string());
+        task_manager.add_task(old);
+        task_manager.set_project_archived("Archive", true).unwrap();
+        assert_eq!(names(&task_manager, "report"), ["Write the quarterly report", "Review code"]);
+    }
+
+    #[test]
+    fn matched_positions() {
+        let task_manager = task_manager();
+        let results = search(&task_manager, "MILK");
+        assert_eq!(results[0].name_matches, (4..8).collect());
+    }
+
+    #[test]
+    fn edit_distance_counts_swaps_as_one() {
+        let chars = |text: &str| text.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("report"), &chars("reprot")), 1);
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+    }
+}
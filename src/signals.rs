@@ -0,0 +1,61 @@
+use std::process::exit;
+use std::sync::{Arc, Mutex};
+use crate::backup::BackedUpStorage;
+use crate::history::HistoryEntry;
+use crate::storage::{lock_saving, Storage, StorageKind};
+use crate::task::Task;
+use crate::task_manager::TaskManager;
+
+/// All tasks if any of them are unsaved, and the history that isn't saved yet.
+struct Unsaved {
+    tasks: Option<Vec<Task>>,
+    history: Vec<HistoryEntry>,
+}
+
+type Snapshot = Arc<Mutex<Option<Unsaved>>>;
+
+/// Copy of what hasn't been saved yet, so it can still be written if the
+/// process is interrupted with Ctrl+C or SIGTERM.
+#[derive(Clone)]
+pub struct UnsavedTasks {
+    snapshot: Snapshot,
+}
+
+impl UnsavedTasks {
+    pub fn update(&self, task_manager: &TaskManager) {
+        let tasks = task_manager.has_unsaved_changes()
+            .then(|| task_manager.get_all_tasks().into_iter().cloned().collect());
+        let history = task_manager.unsaved_history().to_vec();
+        let unsaved = (tasks.is_some() || !history.is_empty()).then_some(Unsaved { tasks, history });
+        if let Ok(mut snapshot) = self.snapshot.lock() {
+            *snapshot = unsaved;
+        }
+    }
+}
+
+/// Installs a SIGINT/SIGTERM handler that saves anything unsaved before
+/// exiting, backing up the data file like any other save. A save already in
+/// progress on the main thread is finished first, and none starts after.
+pub fn save_on_interrupt(kind: StorageKind, file_path: String, backups: usize) -> Result<UnsavedTasks, ctrlc::Error> {
+    let snapshot: Snapshot = Arc::new(Mutex::new(None));
+    let handler_snapshot = Arc::clone(&snapshot);
+    ctrlc::set_handler(move || {
+        // Held until the process exits
+        let _saving = lock_saving();
+        if let Ok(unsaved) = handler_snapshot.lock()
+            && let Some(unsaved) = unsaved.as_ref() {
+            let result = BackedUpStorage::open(kind, file_path.clone(), backups).and_then(|storage| {
+                if let Some(tasks) = &unsaved.tasks {
+                    storage.save_tasks(&tasks.iter().collect::<Vec<&Task>>())?;
+                }
+                storage.append_history(&unsaved.history)
+            });
+            match result {
+                Ok(_) => println!("\nUnsaved changes were saved before exiting."),
+                Err(e) => eprintln!("\nError saving tasks: {}", e),
+            }
+        }
+        exit(130);
+    })?;
+    Ok(UnsavedTasks { snapshot })
+}
@@ -0,0 +1,317 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use rusqlite::{params, Connection, Transaction};
+use crate::history::HistoryEntry;
+use crate::storage::Storage;
+use crate::task::{format_timestamp, parse_timestamp, Task};
+
+/// Schema migrations, applied in order. The database's `user_version` holds the
+/// number of migrations already applied, so new ones must only ever be appended.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE tasks (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        description TEXT,
+        due_date TEXT,
+        priority INTEGER NOT NULL DEFAULT 5,
+        completed INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE tags (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE task_tags (
+        task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        tag_id INTEGER NOT NULL REFERENCES tags(id),
+        PRIMARY KEY (task_id, tag_id)
+    );",
+    // 2: repeating tasks
+    "ALTER TABLE tasks ADD COLUMN recurrence TEXT;",
+    // 3: subtasks (no foreign key, since parents may be written after their subtasks)
+    "ALTER TABLE tasks ADD COLUMN parent_id INTEGER;",
+    // 4: dependencies between tasks
+    "CREATE TABLE task_dependencies (
+        task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        depends_on_id INTEGER NOT NULL,
+        PRIMARY KEY (task_id, depends_on_id)
+    );",
+    // 5: workflow status instead of the completed flag
+    "ALTER TABLE tasks ADD COLUMN status TEXT NOT NULL DEFAULT 'todo';
+    UPDATE tasks SET status = 'done' WHERE completed = 1;
+    ALTER TABLE tasks DROP COLUMN completed;",
+    // 6: timestamps
+    "ALTER TABLE tasks ADD COLUMN created_at TEXT;
+    ALTER TABLE tasks ADD COLUMN updated_at TEXT;
+    ALTER TABLE tasks ADD COLUMN completed_at TEXT;",
+    // 7: change history (kept for deleted tasks too, so no foreign key)
+    "CREATE TABLE history (
+        id INTEGER PRIMARY KEY,
+        task_id INTEGER NOT NULL,
+        field TEXT NOT NULL,
+        old_value TEXT NOT NULL,
+        new_value TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        user TEXT NOT NULL
+    );
+    CREATE INDEX history_task_id ON history(task_id);",
+    // 8: projects
+    "ALTER TABLE tasks ADD COLUMN project TEXT;
+    ALTER TABLE tasks ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;",
+];
+
+pub struct SqliteStorage {
+    connection: Connection,
+}
+
+impl SqliteStorage {
+    pub fn open(file_path: &str) -> io::Result<SqliteStorage> {
+        let connection = Connection::open(file_path).map_err(to_io)?;
+        connection.pragma_update(None, "foreign_keys", true).map_err(to_io)?;
+        let storage = SqliteStorage { connection };
+        storage.migrate().map_err(to_io)?;
+        Ok(storage)
+    }
+
+    fn migrate(&self) -> rusqlite::Result<()> {
+        let version: u32 = self.connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            let transaction = self.connection.unchecked_transaction()?;
+            transaction.execute_batch(migration)?;
+            transaction.pragma_update(None, "user_version", index as u32 + 1)?;
+            transaction.commit()?;
+        }
+        Ok(())
+    }
+
+    fn write_task(transaction: &Transaction, task: &Task) -> rusqlite::Result<()> {
+        transaction.execute(
+            "INSERT INTO tasks (id, name, description, due_date, priority, status, recurrence, parent_id, created_at, updated_at, completed_at, project, archived)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                description = excluded.description,
+                due_date = excluded.due_date,
+                priority = excluded.priority,
+                status = excluded.status,
+                recurrence = excluded.recurrence,
+                parent_id = excluded.parent_id,
+                created_at = excluded.created_at,
+                updated_at = excluded.updated_at,
+                completed_at = excluded.completed_at,
+                project = excluded.project,
+                archived = excluded.archived",
+            params![
+                task.id(),
+                task.name(),
+                task.description(),
+                task.due_date_as_str(),
+                task.priority(),
+                task.status().to_string(),
+                task.recurrence_as_str(),
+                task.parent(),
+                format_timestamp(task.created_at()),
+                format_timestamp(task.updated_at()),
+                task.completed_at().map(format_timestamp),
+                task.project(),
+                task.is_archived(),
+            ],
+        )?;
+
+        transaction.execute("DELETE FROM task_dependencies WHERE task_id = ?1", [task.id()])?;
+        for dependency in task.dependencies() {
+            transaction.execute(
+                "INSERT INTO task_dependencies (task_id, depends_on_id) VALUES (?1, ?2)",
+                params![task.id(), dependency],
+            )?;
+        }
+
+        transaction.execute("DELETE FROM task_tags WHERE task_id = ?1", [task.id()])?;
+        for tag in task.tags() {
+            transaction.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [tag])?;
+            transaction.execute(
+                "INSERT INTO task_tags (task_id, tag_id) SELECT ?1, id FROM tags WHERE name = ?2",
+                params![task.id(), tag],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn remove_unused_tags(transaction: &Transaction) -> rusqlite::Result<()> {
+        transaction.execute("DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM task_tags)", [])?;
+        Ok(())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load_tasks(&self) -> io::Result<Vec<Task>> {
+        let mut tags: HashMap<u32, Vec<String>> = HashMap::new();
+        let mut statement = self.connection.prepare(
+            "SELECT task_tags.task_id, tags.name FROM task_tags JOIN tags ON tags.id = task_tags.tag_id"
+        ).map_err(to_io)?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?)))
+            .map_err(to_io)?;
+        for row in rows {
+            let (task_id, tag) = row.map_err(to_io)?;
+            tags.entry(task_id).or_default().push(tag);
+        }
+
+        let mut dependencies: HashMap<u32, Vec<u32>> = HashMap::new();
+        let mut statement = self.connection.prepare(
+            "SELECT task_id, depends_on_id FROM task_dependencies"
+        ).map_err(to_io)?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, u32>(1)?)))
+            .map_err(to_io)?;
+        for row in rows {
+            let (task_id, dependency) = row.map_err(to_io)?;
+            dependencies.entry(task_id).or_default().push(dependency);
+        }
+
+        let mut statement = self.connection.prepare(
+            "SELECT id, name, description, due_date, priority, status, recurrence, parent_id, created_at, updated_at, completed_at, project, archived FROM tasks ORDER BY id"
+        ).map_err(to_io)?;
+        let mut rows = statement.query([]).map_err(to_io)?;
+        let mut tasks = Vec::new();
+        while let Some(row) = rows.next().map_err(to_io)? {
+            let mut task = Task::new(row.get(1).map_err(to_io)?);
+            task.set_id(row.get(0).map_err(to_io)?);
+
+            // Description
+            if let Some(description) = row.get::<_, Option<String>>(2).map_err(to_io)? {
+                task.set_description(description);
+            }
+            // Due date
+            if let Some(due_date) = row.get::<_, Option<String>>(3).map_err(to_io)?
+                && let Err(e) = task.set_due_date(due_date) {
+                eprintln!("Error setting due date for task '{}': {}", task.name(), e);
+            }
+            // Tags
+            for tag in tags.remove(&task.id()).unwrap_or_default() {
+                task.add_tag(tag);
+            }
+            // Priority
+            if let Err(e) = task.set_priority(row.get(4).map_err(to_io)?) {
+                eprintln!("Error setting priority for task '{}': {}", task.name(), e);
+            }
+            // Status
+            match row.get::<_, String>(5).map_err(to_io)?.parse() {
+                Ok(status) => task.set_status(status),
+                Err(e) => eprintln!("Error setting status for task '{}': {}", task.name(), e),
+            }
+            // Recurrence
+            if let Some(recurrence) = row.get::<_, Option<String>>(6).map_err(to_io)?
+                && let Err(e) = task.set_recurrence(recurrence) {
+                eprintln!("Error setting repeat rule for task '{}': {}", task.name(), e);
+            }
+            // Parent
+            task.set_parent(row.get(7).map_err(to_io)?);
+            // Dependencies
+            for dependency in dependencies.remove(&task.id()).unwrap_or_default() {
+                task.add_dependency(dependency);
+            }
+            // Timestamps (missing for tasks written before they were tracked)
+            if let Some(created_at) = row.get::<_, Option<String>>(8).map_err(to_io)? {
+                match parse_timestamp(&created_at) {
+                    Ok(created_at) => task.set_created_at(created_at),
+                    Err(e) => eprintln!("Error setting creation time for task '{}': {}", task.name(), e),
+                }
+            }
+            if let Some(updated_at) = row.get::<_, Option<String>>(9).map_err(to_io)? {
+                match parse_timestamp(&updated_at) {
+                    Ok(updated_at) => task.set_updated_at(updated_at),
+                    Err(e) => eprintln!("Error setting modification time for task '{}': {}", task.name(), e),
+                }
+            }
+            if let Some(completed_at) = row.get::<_, Option<String>>(10).map_err(to_io)? {
+                match parse_timestamp(&completed_at) {
+                    Ok(completed_at) => task.set_completed_at(Some(completed_at)),
+                    Err(e) => eprintln!("Error setting completion time for task '{}': {}", task.name(), e),
+                }
+            }
+            // Project
+            if let Some(project) = row.get::<_, Option<String>>(11).map_err(to_io)? {
+                task.set_project(project);
+            }
+            task.set_archived(row.get(12).map_err(to_io)?);
+
+            tasks.push(task);
+        }
+        Ok(tasks)
+    }
+
+    fn save_tasks(&self, tasks: &[&Task]) -> io::Result<()> {
+        let transaction = self.connection.unchecked_transaction().map_err(to_io)?;
+        let ids: HashSet<u32> = tasks.iter().map(|task| task.id()).collect();
+        let mut existing = transaction.prepare("SELECT id FROM tasks").map_err(to_io)?;
+        let stale: Vec<u32> = existing.query_map([], |row| row.get(0))
+            .map_err(to_io)?
+            .collect::<rusqlite::Result<Vec<u32>>>()
+            .map_err(to_io)?
+            .into_iter()
+            .filter(|id| !ids.contains(id))
+            .collect();
+        drop(existing);
+
+        for id in stale {
+            transaction.execute("DELETE FROM tasks WHERE id = ?1", [id]).map_err(to_io)?;
+        }
+        for task in tasks {
+            Self::write_task(&transaction, task).map_err(to_io)?;
+        }
+        Self::remove_unused_tags(&transaction).map_err(to_io)?;
+        transaction.commit().map_err(to_io)
+    }
+
+    fn upsert_task(&self, task: &Task) -> io::Result<()> {
+        let transaction = self.connection.unchecked_transaction().map_err(to_io)?;
+        Self::write_task(&transaction, task).map_err(to_io)?;
+        Self::remove_unused_tags(&transaction).map_err(to_io)?;
+        transaction.commit().map_err(to_io)
+    }
+
+    fn delete_task(&self, id: u32) -> io::Result<()> {
+        let transaction = self.connection.unchecked_transaction().map_err(to_io)?;
+        transaction.execute("DELETE FROM tasks WHERE id = ?1", [id]).map_err(to_io)?;
+        Self::remove_unused_tags(&transaction).map_err(to_io)?;
+        transaction.commit().map_err(to_io)
+    }
+
+    fn updates_incrementally(&self) -> bool {
+        true
+    }
+
+    fn load_history(&self) -> io::Result<Vec<HistoryEntry>> {
+        let mut statement = self.connection.prepare(
+            "SELECT task_id, field, old_value, new_value, timestamp, user FROM history ORDER BY id"
+        ).map_err(to_io)?;
+        let mut rows = statement.query([]).map_err(to_io)?;
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next().map_err(to_io)? {
+            let timestamp: String = row.get(4).map_err(to_io)?;
+            entries.push(HistoryEntry {
+                task_id: row.get(0).map_err(to_io)?,
+                field: row.get(1).map_err(to_io)?,
+                old_value: row.get(2).map_err(to_io)?,
+                new_value: row.get(3).map_err(to_io)?,
+                timestamp: parse_timestamp(&timestamp).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+                user: row.get(5).map_err(to_io)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn append_history(&self, entries: &[HistoryEntry]) -> io::Result<()> {
+        let transaction = self.connection.unchecked_transaction().map_err(to_io)?;
+        for entry in entries {
+            transaction.execute(
+                "INSERT INTO history (task_id, field, old_value, new_value, timestamp, user) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![entry.task_id, entry.field, entry.old_value, entry.new_value, format_timestamp(entry.timestamp), entry.user],
+            ).map_err(to_io)?;
+        }
+        transaction.commit().map_err(to_io)
+    }
+}
+
+fn to_io(error: rusqlite::Error) -> io::Error {
+    io::Error::other(error)
+}
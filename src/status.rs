@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Status {
+    Todo,
+    InProgress,
+    Blocked,
+    Done,
+    Cancelled,
+}
+
+impl Status {
+    pub const ALL: [Status; 5] = [Status::Todo, Status::InProgress, Status::Blocked, Status::Done, Status::Cancelled];
+
+    /// Done and cancelled tasks need no more work.
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Status::Done | Status::Cancelled)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Todo => "To do",
+            Status::InProgress => "In progress",
+            Status::Blocked => "Blocked",
+            Status::Done => "Done",
+            Status::Cancelled => "Cancelled",
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_lowercase().replace(['-', ' '], "_").as_str() {
+            "todo" | "to_do" => Ok(Status::Todo),
+            "in_progress" | "inprogress" => Ok(Status::InProgress),
+            "blocked" => Ok(Status::Blocked),
+            "done" => Ok(Status::Done),
+            "cancelled" | "canceled" => Ok(Status::Cancelled),
+            _ => Err(format!("Unknown status '{}'. Use todo, in_progress, blocked, done or cancelled.", input.trim())),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in_progress",
+            Status::Blocked => "blocked",
+            Status::Done => "done",
+            Status::Cancelled => "cancelled",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Which status changes are allowed. Set in the config file with lines like
+/// `transitions.todo = in_progress, done, cancelled`.
+#[derive(Clone)]
+pub struct Workflow {
+    transitions: HashMap<Status, Vec<Status>>,
+}
+
+impl Workflow {
+    pub fn new() -> Self {
+        let mut transitions = HashMap::new();
+        transitions.insert(Status::Todo, vec![Status::InProgress, Status::Blocked, Status::Done, Status::Cancelled]);
+        transitions.insert(Status::InProgress, vec![Status::Todo, Status::Blocked, Status::Done, Status::Cancelled]);
+        transitions.insert(Status::Blocked, vec![Status::Todo, Status::InProgress, Status::Done, Status::Cancelled]);
+        transitions.insert(Status::Done, vec![Status::Todo]);
+        transitions.insert(Status::Cancelled, vec![Status::Todo]);
+        Workflow { transitions }
+    }
+
+    pub fn set_transitions(&mut self, from: Status, to: Vec<Status>) {
+        self.transitions.insert(from, to);
+    }
+
+    pub fn allowed_from(&self, from: Status) -> &[Status] {
+        self.transitions.get(&from).map(|to| to.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn check(&self, from: Status, to: Status) -> Result<(), String> {
+        if from == to || self.allowed_from(from).contains(&to) {
+            Ok(())
+        } else {
+            Err(format!("Can't change status from {} to {}.", from, to))
+        }
+    }
+}
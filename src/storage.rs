@@ -0,0 +1,162 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use clap::ValueEnum;
+use crate::csv_handler::CsvHandler;
+use crate::history::HistoryEntry;
+use crate::json_storage::JsonStorage;
+use crate::sqlite_storage::SqliteStorage;
+use crate::task::Task;
+use crate::task_manager::TaskManager;
+
+/// A place tasks are loaded from and saved to.
+pub trait Storage {
+    fn load_tasks(&self) -> io::Result<Vec<Task>>;
+
+    /// Replaces everything in the storage with the given tasks.
+    fn save_tasks(&self, tasks: &[&Task]) -> io::Result<()>;
+
+    /// Inserts or replaces a single task. Backends that can't update a single
+    /// record rewrite everything.
+    fn upsert_task(&self, task: &Task) -> io::Result<()> {
+        let mut tasks = self.load_tasks()?;
+        match tasks.iter_mut().find(|existing| existing.id() == task.id()) {
+            Some(existing) => *existing = task.clone(),
+            None => tasks.push(task.clone()),
+        }
+        self.save_tasks(&tasks.iter().collect::<Vec<&Task>>())
+    }
+
+    fn delete_task(&self, id: u32) -> io::Result<()> {
+        let tasks = self.load_tasks()?;
+        self.save_tasks(&tasks.iter().filter(|task| task.id() != id).collect::<Vec<&Task>>())
+    }
+
+    /// Whether `upsert_task` and `delete_task` are cheap enough to write each
+    /// change on its own instead of rewriting everything.
+    fn updates_incrementally(&self) -> bool {
+        false
+    }
+
+    /// Recorded field changes, oldest first.
+    fn load_history(&self) -> io::Result<Vec<HistoryEntry>>;
+
+    /// Adds entries to the end of the history. Stored entries are never changed.
+    fn append_history(&self, entries: &[HistoryEntry]) -> io::Result<()>;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum StorageKind {
+    Csv,
+    Json,
+    Sqlite,
+}
+
+impl StorageKind {
+    pub fn default_file(&self) -> &'static str {
+        match self {
+            StorageKind::Csv => "tasks.csv",
+            StorageKind::Json => "tasks.json",
+            StorageKind::Sqlite => "tasks.db",
+        }
+    }
+
+    /// Guesses the backend from a file extension.
+    pub fn from_path(path: &str) -> Option<StorageKind> {
+        match Path::new(path).extension()?.to_str()?.to_lowercase().as_str() {
+            "csv" => Some(StorageKind::Csv),
+            "json" => Some(StorageKind::Json),
+            "db" | "sqlite" | "sqlite3" => Some(StorageKind::Sqlite),
+            _ => None,
+        }
+    }
+}
+
+pub fn open_storage(kind: StorageKind, file_path: String) -> io::Result<Box<dyn Storage>> {
+    match kind {
+        StorageKind::Csv => Ok(Box::new(CsvHandler::new(file_path))),
+        StorageKind::Json => Ok(Box::new(JsonStorage::new(file_path))),
+        StorageKind::Sqlite => Ok(Box::new(SqliteStorage::open(&file_path)?)),
+    }
+}
+
+/// Held while tasks are being written, so the interrupt handler never writes
+/// the same files at the same time as the main thread.
+static SAVING: Mutex<()> = Mutex::new(());
+
+pub fn lock_saving() -> MutexGuard<'static, ()> {
+    // Nothing is left half-done by a panic while saving, as files are replaced atomically
+    SAVING.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Writes the tasks changed since the last call. Changes stay pending if saving fails.
+pub fn persist_changes(task_manager: &mut TaskManager, storage: &dyn Storage) -> io::Result<()> {
+    let _saving = lock_saving();
+    if !storage.updates_incrementally() {
+        if task_manager.has_unsaved_changes() {
+            storage.save_tasks(&task_manager.get_all_tasks())?;
+            task_manager.mark_all_saved();
+        }
+    } else {
+        let mut saved = Vec::new();
+        let result: io::Result<()> = task_manager.changed_task_ids().into_iter().try_for_each(|id| {
+            match task_manager.get_task(id) {
+                Some(task) => storage.upsert_task(task)?,
+                None => storage.delete_task(id)?,
+            }
+            saved.push(id);
+            Ok(())
+        });
+        task_manager.mark_saved_many(&saved);
+        result?;
+    }
+    save_history(task_manager, storage)
+}
+
+/// Writes every task, and the history recorded since the last save.
+pub fn save_all(task_manager: &mut TaskManager, storage: &dyn Storage) -> io::Result<()> {
+    let _saving = lock_saving();
+    storage.save_tasks(&task_manager.get_all_tasks())?;
+    task_manager.mark_all_saved();
+    save_history(task_manager, storage)
+}
+
+// Only called once the tasks are saved, so the history never describes
+// changes that didn't make it to the file
+fn save_history(task_manager: &mut TaskManager, storage: &dyn Storage) -> io::Result<()> {
+    storage.append_history(task_manager.unsaved_history())?;
+    task_manager.mark_history_saved();
+    Ok(())
+}
+
+/// Writes a file so that it is never left half-written: the content goes to a
+/// temporary file in the same directory, which is synced and then renamed over
+/// the original.
+pub fn write_atomically<P, F>(path: P, write: F) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    let path = path.as_ref();
+    let file_name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    let result = File::create(&temp_path).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()
+    });
+    if let Err(e) = result.and_then(|_| fs::rename(&temp_path, path)) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Some(directory) = path.parent() {
+        let directory = if directory.as_os_str().is_empty() { Path::new(".") } else { directory };
+        File::open(directory)?.sync_all()?;
+    }
+    Ok(())
+}
@@ -1,29 +1,63 @@
-use std::collections::HashSet;
-use chrono::{NaiveDate, Local};
+use std::collections::{BTreeSet, HashSet};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeDelta, TimeZone, Utc};
 use colored::Colorize;
+use crate::date_parser::parse_relative_date;
+use crate::recurrence::Recurrence;
+use crate::status::Status;
+
+/// When a task is due: some time during a day, or at an exact moment. The
+/// moment keeps the UTC offset it was entered with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Due {
+    Date(NaiveDate),
+    At(DateTime<FixedOffset>),
+}
 
 #[derive(Clone)]
 pub struct Task {
+    id: u32,
     name: String,
     description: Option<String>,
-    due_date: Option<NaiveDate>,
+    due: Option<Due>,
     tags: HashSet<String>,
     priority: u8,
-    completed: bool,
+    status: Status,
+    recurrence: Option<Recurrence>,
+    parent: Option<u32>,
+    dependencies: BTreeSet<u32>,
+    project: Option<String>,
+    archived: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
 }
 impl Task {
     pub fn new(name: String) -> Self {
+        let now = Utc::now();
         Task {
+            id: 0, // Assigned by TaskManager
             name,
             description: None,
-            due_date: None,
+            due: None,
             tags: HashSet::new(),
             priority: 5, // Default priority
-            completed: false,
+            status: Status::Todo,
+            recurrence: None,
+            parent: None,
+            dependencies: BTreeSet::new(),
+            project: None,
+            archived: false,
+            created_at: now,
+            updated_at: now,
+            completed_at: None,
         }
     }
 
     // Getters
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
     pub fn name(&self) -> &String {
         &self.name
     }
@@ -32,12 +66,47 @@ impl Task {
         self.description.as_deref()
     }
 
+    /// The due date as stored, e.g. `2025-01-31` or `2025-01-31 14:00 +02:00`.
     pub fn due_date_as_str(&self) -> Option<String> {
-        self.due_date.map(|date| date.format("%Y-%m-%d").to_string())
+        self.due.map(|due| match due {
+            Due::Date(date) => date.format("%Y-%m-%d").to_string(),
+            Due::At(at) => at.format("%Y-%m-%d %H:%M %:z").to_string(),
+        })
+    }
+
+    /// The due date and time (if any) in the local time zone.
+    pub fn due_as_local_str(&self) -> Option<String> {
+        self.due.map(|due| match due {
+            Due::Date(date) => date.format("%Y-%m-%d").to_string(),
+            Due::At(at) => at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+        })
+    }
+
+    pub fn due(&self) -> Option<Due> {
+        self.due
     }
 
+    /// The day the task is due on in the local time zone.
     pub fn due_date(&self) -> Option<NaiveDate> {
-        self.due_date
+        self.due.map(|due| match due {
+            Due::Date(date) => date,
+            Due::At(at) => at.with_timezone(&Local).date_naive(),
+        })
+    }
+
+    /// The moment the task becomes overdue. Tasks due on a date are due until
+    /// the end of that day.
+    pub fn deadline(&self) -> Option<DateTime<Local>> {
+        self.due.map(|due| match due {
+            Due::Date(date) => {
+                // The last representable day ends a day early rather than overflowing
+                let midnight = date.succ_opt().unwrap_or(date).and_hms_opt(0, 0, 0).unwrap();
+                // Midnight can be skipped by a DST change
+                Local.from_local_datetime(&midnight).earliest()
+                    .unwrap_or_else(|| Local.from_utc_datetime(&midnight))
+            }
+            Due::At(at) => at.with_timezone(&Local),
+        })
     }
 
     pub fn tags(&self) -> &HashSet<String> {
@@ -60,22 +129,134 @@ impl Task {
         print!("]");
     }
 
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
     pub fn completed(&self) -> bool {
-        self.completed
+        self.status == Status::Done
+    }
+
+    /// Not done or cancelled yet.
+    pub fn is_pending(&self) -> bool {
+        !self.status.is_closed()
+    }
+
+    pub fn recurrence(&self) -> Option<&Recurrence> {
+        self.recurrence.as_ref()
+    }
+
+    pub fn recurrence_as_str(&self) -> Option<String> {
+        self.recurrence.as_ref().map(|recurrence| recurrence.to_string())
+    }
+
+    pub fn parent(&self) -> Option<u32> {
+        self.parent
+    }
+
+    /// IDs of the tasks that have to be completed before this one can start.
+    pub fn dependencies(&self) -> &BTreeSet<u32> {
+        &self.dependencies
+    }
+
+    pub fn dependencies_csv(&self) -> String {
+        self.dependencies.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(",")
+    }
+
+    pub fn project(&self) -> Option<&str> {
+        self.project.as_deref()
+    }
+
+    /// Archived tasks belong to an archived project and are left out of the
+    /// usual task lists.
+    pub fn is_archived(&self) -> bool {
+        self.archived
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    pub fn completed_at(&self) -> Option<DateTime<Utc>> {
+        self.completed_at
+    }
+
+    /// Time from creation to completion.
+    pub fn cycle_time(&self) -> Option<TimeDelta> {
+        self.completed_at.map(|completed_at| completed_at - self.created_at)
+    }
+
+    pub fn is_overdue(&self) -> bool {
+        self.is_pending() && self.deadline().is_some_and(|deadline| deadline < Local::now())
+    }
+
+    pub fn is_due_today(&self) -> bool {
+        self.due_date() == Some(Local::now().date_naive())
     }
 
     // Setters
+    pub fn set_id(&mut self, id: u32) {
+        self.id = id;
+    }
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
     pub fn set_description(&mut self, description: String) {
         self.description = Some(description);
     }
+    pub fn clear_description(&mut self) {
+        self.description = None;
+    }
+    /// Accepts `YYYY-MM-DD`, optionally followed by `HH:MM` and a UTC offset
+    /// such as `+02:00` or `Z`, or a relative date like `next friday 14:00`
+    /// (see `parse_relative_date`). Times without an offset are in local time.
     pub fn set_due_date(&mut self, due_date: String) -> Result<(), String> {
-        match NaiveDate::parse_from_str(&due_date, "%Y-%m-%d") {
-            Ok(date) => {
-                self.due_date = Some(date);
-                Ok(())
-            },
-            Err(_) => Err("Invalid date format. Use YYYY-MM-DD.".to_string()),
+        let due_date = due_date.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(due_date, "%Y-%m-%d") {
+            self.due = Some(Due::Date(date));
+            return Ok(());
+        }
+
+        let utc = due_date.strip_suffix('Z').or_else(|| due_date.strip_suffix("UTC"))
+            .map(|without_zone| format!("{} +00:00", without_zone.trim()));
+        let with_offset = utc.as_deref().unwrap_or(due_date);
+        if let Ok(at) = DateTime::parse_from_str(with_offset, "%Y-%m-%d %H:%M %:z")
+            .or_else(|_| DateTime::parse_from_str(with_offset, "%Y-%m-%d %H:%M %z")) {
+            self.due = Some(Due::At(at));
+            return Ok(());
+        }
+        if let Ok(local) = NaiveDateTime::parse_from_str(due_date, "%Y-%m-%d %H:%M") {
+            self.due = Some(local_due(local)?);
+            return Ok(());
         }
+
+        let (expression, time) = match due_date.rsplit_once(' ').map(|(expression, time)| (expression, NaiveTime::parse_from_str(time, "%H:%M"))) {
+            Some((expression, Ok(time))) => (expression, Some(time)),
+            _ => (due_date, None),
+        };
+        let date = parse_relative_date(expression, Local::now().date_naive())?;
+        self.due = Some(match time {
+            Some(time) => local_due(date.and_time(time))?,
+            None => Due::Date(date),
+        });
+        Ok(())
+    }
+    pub fn clear_due_date(&mut self) {
+        self.due = None;
+    }
+    /// Moves the due date by a number of days (back if negative), keeping any time.
+    pub fn shift_due(&mut self, days: i64) -> Result<(), String> {
+        let shift = TimeDelta::try_days(days).ok_or("Too many days.")?;
+        let shifted = match self.due.ok_or("Task has no due date.")? {
+            Due::Date(date) => date.checked_add_signed(shift).map(Due::Date),
+            Due::At(at) => at.checked_add_signed(shift).map(Due::At),
+        };
+        self.due = Some(shifted.ok_or("Date out of range.")?);
+        Ok(())
     }
     pub fn add_tag(&mut self, tag: String) {
         self.tags.insert(tag);
@@ -84,6 +265,9 @@ impl Task {
     pub fn remove_tag(&mut self, tag: &str) {
         self.tags.remove(tag);
     }
+    pub fn set_tags(&mut self, tags: HashSet<String>) {
+        self.tags = tags;
+    }
     pub fn set_priority(&mut self, priority: u8) -> Result<(), String> {
         if priority <= 10 {
             self.priority = priority;
@@ -93,7 +277,96 @@ impl Task {
         }
     }
     pub fn mark_completed(&mut self) {
-        self.completed = true;
+        self.status = Status::Done;
+    }
+    /// Use `TaskManager::set_status` for tasks already in a manager, which
+    /// also checks the workflow.
+    pub fn set_status(&mut self, status: Status) {
+        self.status = status;
+    }
+    pub fn set_recurrence(&mut self, recurrence: String) -> Result<(), String> {
+        self.recurrence = Some(recurrence.parse()?);
+        Ok(())
+    }
+    pub fn clear_recurrence(&mut self) {
+        self.recurrence = None;
+    }
+    /// Use `TaskManager::set_parent` for tasks already in a manager, which
+    /// also guards against cycles.
+    pub fn set_parent(&mut self, parent: Option<u32>) {
+        self.parent = parent;
+    }
+    /// Use `TaskManager::add_dependency` for tasks already in a manager, which
+    /// also guards against cycles.
+    pub fn add_dependency(&mut self, id: u32) {
+        self.dependencies.insert(id);
+    }
+    pub fn remove_dependency(&mut self, id: u32) {
+        self.dependencies.remove(&id);
+    }
+    pub fn set_project(&mut self, project: String) {
+        self.project = Some(project);
+    }
+    pub fn clear_project(&mut self) {
+        self.project = None;
+    }
+    /// Use `TaskManager::set_project_archived` for tasks already in a manager,
+    /// which archives the whole project.
+    pub fn set_archived(&mut self, archived: bool) {
+        self.archived = archived;
+    }
+    pub fn set_created_at(&mut self, created_at: DateTime<Utc>) {
+        self.created_at = created_at;
+    }
+    pub fn set_updated_at(&mut self, updated_at: DateTime<Utc>) {
+        self.updated_at = updated_at;
+    }
+    pub fn set_completed_at(&mut self, completed_at: Option<DateTime<Utc>>) {
+        self.completed_at = completed_at;
+    }
+    /// Records that the task was just modified.
+    pub fn touch(&mut self) {
+        self.updated_at = Utc::now();
+    }
+
+    /// Creates the next instance of a repeating task, due on the first
+    /// occurrence after the current due date (or today) that isn't in the past.
+    /// Returns `Ok(None)` for tasks that don't repeat.
+    pub fn next_occurrence(&self) -> Result<Option<Task>, String> {
+        let today = Local::now().date_naive();
+        // Timed tasks repeat at the same time of day in the same offset
+        let (start, time) = match self.due {
+            Some(Due::Date(date)) => (date, None),
+            Some(Due::At(at)) => (at.date_naive(), Some((at.time(), *at.offset()))),
+            None => (today, None),
+        };
+        let Some(recurrence) = self.recurrence.as_ref() else {
+            return Ok(None);
+        };
+        let recurrence = match recurrence {
+            // Pin the day so it doesn't drift after a short month
+            Recurrence::Monthly(None) => Recurrence::Monthly(Some(start.day())),
+            recurrence => recurrence.clone(),
+        };
+        let mut due_date = recurrence.next_after(start)?;
+        while due_date < today {
+            due_date = recurrence.next_after(due_date)?;
+        }
+
+        let now = Utc::now();
+        let mut next = self.clone();
+        next.id = 0;
+        next.status = Status::Todo;
+        next.created_at = now;
+        next.updated_at = now;
+        next.completed_at = None;
+        next.due = Some(match time {
+            Some((time, offset)) => Due::At(offset.from_local_datetime(&due_date.and_time(time)).single()
+                .ok_or("The next occurrence is out of range.")?),
+            None => Due::Date(due_date),
+        });
+        next.recurrence = Some(recurrence);
+        Ok(Some(next))
     }
 
     pub fn tags_csv(&self) -> String {
@@ -101,4 +374,21 @@ impl Task {
         tags.sort();
         tags.join(",")
     }
+}
+
+fn local_due(local: NaiveDateTime) -> Result<Due, String> {
+    let at = Local.from_local_datetime(&local).earliest()
+        .ok_or("This time doesn't exist in the local time zone.")?;
+    Ok(Due::At(at.fixed_offset()))
+}
+
+/// Timestamps are stored as RFC 3339 in UTC, e.g. `2025-01-31T17:45:00Z`.
+pub fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+pub fn parse_timestamp(input: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(input.trim())
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|_| format!("Invalid timestamp '{}'.", input.trim()))
 }
\ No newline at end of file
@@ -1,77 +1,782 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+use chrono::Utc;
+use crate::history::{current_user, field_changes, HistoryEntry};
+use crate::status::{Status, Workflow};
 use crate::task::Task;
 
+/// What else happened when a task's status was changed.
+#[derive(Default)]
+pub struct StatusChange {
+    /// ID of the next occurrence created for a completed repeating task
+    pub next_occurrence: Option<u32>,
+    /// Pending subtasks that were closed along with the task
+    pub closed_subtasks: Vec<u32>,
+    /// Tasks that were waiting on this one and can now be started
+    pub unblocked: Vec<u32>,
+}
+
+type ChangeCallback = Box<dyn Fn(&TaskManager)>;
+
+/// Most changes kept for undo
+const UNDO_LIMIT: usize = 100;
+
+/// A change that can be undone: every task it touched as it was before and
+/// after (`None` where the task didn't exist).
+struct Revision {
+    label: String,
+    before: Vec<(u32, Option<Task>)>,
+    after: Vec<(u32, Option<Task>)>,
+}
+
+// The tasks as they were when a change started, and the ones it touched so far
+struct Recording {
+    snapshot: HashMap<u32, Task>,
+    touched: BTreeSet<u32>,
+}
+
 pub struct TaskManager {
-    tasks: HashMap<String, Task>,
+    tasks: HashMap<u32, Task>,
+    next_id: u32,
+    // IDs of tasks added, changed or removed since they were last saved
+    changed: BTreeSet<u32>,
+    workflow: Workflow,
+    undo_stack: Vec<Revision>,
+    redo_stack: Vec<Revision>,
+    recording: Option<Recording>,
+    // Every recorded field change, oldest first; the first `saved_history` are stored
+    history: Vec<HistoryEntry>,
+    saved_history: usize,
+    user: String,
+    // Called whenever the tasks or what is saved of them change
+    on_change: Option<ChangeCallback>,
 }
 
 impl TaskManager {
     pub fn new() -> Self {
         TaskManager {
             tasks: HashMap::new(),
+            next_id: 1,
+            changed: BTreeSet::new(),
+            workflow: Workflow::new(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            recording: None,
+            history: Vec::new(),
+            saved_history: 0,
+            user: current_user(),
+            on_change: None,
+        }
+    }
+
+    pub fn set_workflow(&mut self, workflow: Workflow) {
+        self.workflow = workflow;
+    }
+
+    pub fn workflow(&self) -> &Workflow {
+        &self.workflow
+    }
+
+    /// Registers a function to call after every change and every save, e.g.
+    /// to keep a copy of the unsaved tasks up to date.
+    pub fn on_change(&mut self, callback: impl Fn(&TaskManager) + 'static) {
+        self.on_change = Some(Box::new(callback));
+    }
+
+    fn notify(&self) {
+        if let Some(callback) = &self.on_change {
+            callback(self);
+        }
+    }
+
+    /// Adds tasks read from storage. Only tasks that had to be given a new ID
+    /// count as changed.
+    pub fn load_tasks(&mut self, tasks: Vec<Task>) {
+        for task in tasks {
+            let original_id = task.id();
+            let id = self.insert_task(task);
+            if id == original_id {
+                self.changed.remove(&id);
+            }
+        }
+
+        // Hand-edited files could make a task its own ancestor
+        let ids: Vec<u32> = self.tasks.keys().copied().collect();
+        self.break_parent_cycles(&ids);
+    }
+
+    // Drops the parent of any of these tasks that turns out to be its own ancestor
+    fn break_parent_cycles(&mut self, ids: &[u32]) {
+        for &id in ids {
+            let mut seen = vec![id];
+            let mut current = self.tasks[&id].parent();
+            while let Some(parent) = current {
+                if seen.contains(&parent) {
+                    self.tasks.get_mut(&id).unwrap().set_parent(None);
+                    self.mark_changed(id);
+                    break;
+                }
+                seen.push(parent);
+                current = self.tasks.get(&parent).and_then(|task| task.parent());
+            }
+        }
+    }
+
+    /// Adds a task and returns its ID. Tasks without an ID (or with one that is
+    /// already taken or is the highest possible) get a freshly generated one.
+    pub fn add_task(&mut self, mut task: Task) -> u32 {
+        // Tasks added to an archived project are archived along with it
+        if let Some(project) = task.project() {
+            task.set_archived(self.is_project_archived(project));
+        }
+        self.record(format!("Add '{}'", task.name()), |manager| manager.insert_task(task))
+    }
+
+    fn insert_task(&mut self, mut task: Task) -> u32 {
+        // An ID with nothing after it would leave no ID for the next task
+        if task.id() == 0 || task.id().checked_add(1).is_none() || self.tasks.contains_key(&task.id()) {
+            task.set_id(self.free_id());
+        }
+        let id = task.id();
+        self.next_id = self.next_id.max(id + 1);
+        self.tasks.insert(id, task);
+        self.mark_changed(id);
+        id
+    }
+
+    // Always below u32::MAX, so `id + 1` can't overflow
+    fn free_id(&self) -> u32 {
+        if self.next_id < u32::MAX {
+            return self.next_id;
         }
+        // Only once every ID has been given out; deleted tasks' IDs are reused then
+        (1..u32::MAX).find(|id| !self.tasks.contains_key(id)).expect("no task IDs left")
     }
 
-    pub fn add_task(&mut self, task: Task) {
-        self.tasks.insert(task.name().to_string(), task);
+    /// Adds tasks from another file as one change. Tasks whose ID is taken get
+    /// a new one, and parents and dependencies are pointed at the new IDs;
+    /// ones that refer to tasks outside the file, and parents that would make
+    /// a task its own ancestor, are dropped. Tasks that were
+    /// imported before (same name and creation time) are skipped. Returns how
+    /// many tasks were added and skipped.
+    pub fn import_tasks(&mut self, tasks: Vec<Task>) -> (usize, usize) {
+        self.record(format!("Import {} task(s)", tasks.len()), |manager| {
+            let mut new_ids: HashMap<u32, u32> = HashMap::new();
+            let mut added = Vec::new();
+            for task in &tasks {
+                let existing = manager.tasks.values()
+                    .find(|existing| existing.name() == task.name() && existing.created_at() == task.created_at())
+                    .map(|existing| existing.id());
+                let id = match existing {
+                    Some(id) => id,
+                    None => {
+                        let mut copy = task.clone();
+                        copy.set_parent(None);
+                        for dependency in task.dependencies() {
+                            copy.remove_dependency(*dependency);
+                        }
+                        let id = manager.insert_task(copy);
+                        added.push((id, task));
+                        id
+                    }
+                };
+                if task.id() != 0 {
+                    new_ids.insert(task.id(), id);
+                }
+            }
+
+            // Links are restored once every task has its final ID
+            for (id, task) in &added {
+                let imported = manager.tasks.get_mut(id).unwrap();
+                imported.set_parent(task.parent().and_then(|parent| new_ids.get(&parent).copied()));
+                for dependency in task.dependencies() {
+                    if let Some(dependency) = new_ids.get(dependency) {
+                        imported.add_dependency(*dependency);
+                    }
+                }
+            }
+            // The file may have tasks that are each other's parents
+            let ids: Vec<u32> = added.iter().map(|(id, _)| *id).collect();
+            manager.break_parent_cycles(&ids);
+            (added.len(), tasks.len() - added.len())
+        })
     }
 
-    pub fn remove_task(&mut self, name: &str) -> Option<Task> {
-        self.tasks.remove(name)
+    /// Replaces the stored task with the same ID. Returns false if there is no such task.
+    /// A task moved to another project takes on whether that project is archived.
+    pub fn update_task(&mut self, mut task: Task) -> bool {
+        if let Some(existing) = self.tasks.get(&task.id())
+            && existing.project() != task.project() {
+            task.set_archived(task.project().is_some_and(|project| self.is_project_archived(project)));
+        }
+        self.record(format!("Edit '{}'", task.name()), |manager| {
+            match manager.tasks.get_mut(&task.id()) {
+                Some(existing) => {
+                    *existing = task;
+                    existing.touch();
+                    let id = existing.id();
+                    manager.mark_changed(id);
+                    true
+                }
+                None => false,
+            }
+        })
     }
 
-    pub fn get_task(&self, name: &str) -> Option<&Task> {
-        self.tasks.get(name)
+    /// Removes a task together with all of its subtasks. Tasks that depended
+    /// on a removed task no longer wait for it.
+    pub fn remove_task(&mut self, id: u32) -> Option<Task> {
+        let label = format!("Delete '{}'", self.tasks.get(&id)?.name());
+        self.record(label, |manager| manager.delete_task(id))
+    }
+
+    fn delete_task(&mut self, id: u32) -> Option<Task> {
+        let descendants = self.descendants_of(id);
+        let removed = self.tasks.remove(&id)?;
+        let mut removed_ids = vec![id];
+        for descendant in descendants {
+            self.tasks.remove(&descendant);
+            removed_ids.push(descendant);
+        }
+        for removed_id in &removed_ids {
+            self.mark_changed(*removed_id);
+        }
+
+        let mut dependents = Vec::new();
+        for task in self.tasks.values_mut() {
+            if removed_ids.iter().any(|removed_id| task.dependencies().contains(removed_id)) {
+                for removed_id in &removed_ids {
+                    task.remove_dependency(*removed_id);
+                }
+                task.touch();
+                dependents.push(task.id());
+            }
+        }
+        for id in dependents {
+            self.mark_changed(id);
+        }
+        Some(removed)
+    }
+
+    /// Removes every task.
+    pub fn clear_tasks(&mut self) {
+        self.record("Clear all tasks".to_string(), |manager| {
+            let ids: Vec<u32> = manager.tasks.keys().copied().collect();
+            manager.tasks.clear();
+            for id in ids {
+                manager.mark_changed(id);
+            }
+        })
+    }
+
+    pub fn get_task(&self, id: u32) -> Option<&Task> {
+        self.tasks.get(&id)
+    }
+
+    pub fn find_task_by_name(&self, name: &str) -> Option<&Task> {
+        self.tasks.values()
+            .filter(|task| task.name() == name)
+            .min_by_key(|task| task.id())
     }
 
     pub fn get_all_tasks(&self) -> Vec<&Task> {
-        self.tasks.values().collect()
+        let mut tasks: Vec<&Task> = self.tasks.values().collect();
+        tasks.sort_by_key(|task| task.id());
+        tasks
     }
 
     pub fn get_all_pending_tasks_with_due_date(&self) -> Vec<&Task> {
         let mut tasks_with_due_date: Vec<&Task> = self.tasks.values()
             .filter(|task| task.due_date().is_some())
-            .filter(|task| !task.completed())
+            .filter(|task| task.is_pending() && !task.is_archived())
             .collect();
 
-        tasks_with_due_date.sort_by_key(|task| task.due_date().unwrap());
+        tasks_with_due_date.sort_by_key(|task| (task.deadline().unwrap(), task.id()));
 
         tasks_with_due_date
     }
 
     pub fn get_all_pending_tasks_without_due_date(&self) -> Vec<&Task> {
-        self.tasks.values()
+        let mut tasks: Vec<&Task> = self.tasks.values()
             .filter(|task| task.due_date().is_none())
-            .filter(|task| !task.completed())
+            .filter(|task| task.is_pending() && !task.is_archived())
+            .collect();
+        tasks.sort_by_key(|task| task.id());
+        tasks
+    }
+
+    pub fn pending_task_count(&self) -> usize {
+        self.tasks.values().filter(|task| task.is_pending() && !task.is_archived()).count()
+    }
+
+    pub fn list_completed_tasks(&self) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self.tasks.values()
+            .filter(|task| task.status() == Status::Done && !task.is_archived())
+            .collect();
+        tasks.sort_by_key(|task| task.id());
+        tasks
+    }
+
+    /// Completed tasks, most recently completed first.
+    pub fn list_completed_tasks_by_completion_time(&self) -> Vec<&Task> {
+        let mut tasks = self.list_completed_tasks();
+        tasks.sort_by_key(|task| Reverse(task.completed_at()));
+        tasks
+    }
+
+    /// Marks a task as done. See `set_status`.
+    pub fn mark_task_completed(&mut self, id: u32) -> Result<StatusChange, String> {
+        self.set_status(id, Status::Done)
+    }
+
+    /// Changes a task's status if the workflow allows it. Closing a task closes
+    /// its pending subtasks the same way, and completing a repeating task adds
+    /// its next occurrence as a new task.
+    pub fn set_status(&mut self, id: u32, status: Status) -> Result<StatusChange, String> {
+        let task = self.tasks.get(&id).ok_or("Task not found.")?;
+        let label = match status {
+            Status::Done => format!("Complete '{}'", task.name()),
+            Status::Todo if !task.is_pending() => format!("Reopen '{}'", task.name()),
+            _ => format!("Set '{}' to {}", task.name(), status),
+        };
+        self.record(label, |manager| manager.change_status(id, status))
+    }
+
+    fn change_status(&mut self, id: u32, status: Status) -> Result<StatusChange, String> {
+        let current = self.tasks.get(&id).ok_or("Task not found.")?.status();
+        let mut change = StatusChange::default();
+        if current == status {
+            return Ok(change);
+        }
+        self.workflow.check(current, status)?;
+        // Checked before anything changes, so a task whose next occurrence
+        // can't be scheduled stays open
+        let next = if status == Status::Done { self.tasks[&id].next_occurrence()? } else { None };
+
+        // Closing subtasks too can unblock more than the task's own dependents
+        let blocked_before: Vec<u32> = self.list_blocked_tasks().iter().map(|task| task.id()).collect();
+        self.close_or_reopen(id, status);
+
+        if status.is_closed() {
+            // A finished task can't have unfinished steps
+            for descendant in self.descendants_of(id) {
+                let subtask = self.tasks.get_mut(&descendant).unwrap();
+                if subtask.is_pending() {
+                    self.close_or_reopen(descendant, status);
+                    change.closed_subtasks.push(descendant);
+                }
+            }
+        }
+
+        if let Some(next) = next {
+            change.next_occurrence = Some(self.insert_task(next));
+        }
+
+        change.unblocked = blocked_before.into_iter()
+            .filter(|blocked| self.tasks[blocked].is_pending() && !self.is_blocked(&self.tasks[blocked]))
+            .collect();
+        Ok(change)
+    }
+
+    // Sets the status and keeps the completion time in step with it
+    fn close_or_reopen(&mut self, id: u32, status: Status) {
+        let task = self.tasks.get_mut(&id).unwrap();
+        task.set_status(status);
+        task.set_completed_at(if status == Status::Done { Some(Utc::now()) } else { None });
+        task.touch();
+        self.mark_changed(id);
+    }
+
+    /// Sets a closed task back to to-do.
+    pub fn reopen_task(&mut self, id: u32) -> Result<StatusChange, String> {
+        self.set_status(id, Status::Todo)
+    }
+
+    pub fn list_tasks_with_status(&self, statuses: &[Status]) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self.tasks.values()
+            .filter(|task| statuses.contains(&task.status()) && !task.is_archived())
+            .collect();
+        tasks.sort_by_key(|task| task.id());
+        tasks
+    }
+
+    /// Makes a task wait for another one to be completed first.
+    pub fn add_dependency(&mut self, id: u32, dependency: u32) -> Result<(), String> {
+        if !self.tasks.contains_key(&id) || !self.tasks.contains_key(&dependency) {
+            return Err("Task not found.".to_string());
+        }
+        if id == dependency || self.depends_on(dependency, id) {
+            return Err("This dependency would create a cycle.".to_string());
+        }
+        self.record(format!("Add a dependency to '{}'", self.tasks[&id].name()), |manager| {
+            let task = manager.tasks.get_mut(&id).unwrap();
+            task.add_dependency(dependency);
+            task.touch();
+            manager.mark_changed(id);
+        });
+        Ok(())
+    }
+
+    pub fn remove_dependency(&mut self, id: u32, dependency: u32) {
+        let Some(task) = self.tasks.get(&id) else {
+            return;
+        };
+        self.record(format!("Remove a dependency from '{}'", task.name()), |manager| {
+            let task = manager.tasks.get_mut(&id).unwrap();
+            task.remove_dependency(dependency);
+            task.touch();
+            manager.mark_changed(id);
+        })
+    }
+
+    /// Replaces all of a task's dependencies as one change. Nothing changes
+    /// if any of them is missing or would create a cycle.
+    pub fn set_dependencies(&mut self, id: u32, dependencies: &[u32]) -> Result<(), String> {
+        let task = self.tasks.get(&id).ok_or("Task not found.")?;
+        for &dependency in dependencies {
+            if !self.tasks.contains_key(&dependency) {
+                return Err(format!("Task {} not found.", dependency));
+            }
+            if id == dependency || self.depends_on(dependency, id) {
+                return Err(format!("A dependency on {} would create a cycle.", dependency));
+            }
+        }
+        self.record(format!("Change the dependencies of '{}'", task.name()), |manager| {
+            let task = manager.tasks.get_mut(&id).unwrap();
+            for dependency in task.dependencies().clone() {
+                task.remove_dependency(dependency);
+            }
+            for &dependency in dependencies {
+                task.add_dependency(dependency);
+            }
+            task.touch();
+            manager.mark_changed(id);
+        });
+        Ok(())
+    }
+
+    /// Whether `id` depends on `other`, directly or through other tasks.
+    fn depends_on(&self, id: u32, other: u32) -> bool {
+        let mut seen = Vec::new();
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            if current == other {
+                return true;
+            }
+            if seen.contains(&current) {
+                continue;
+            }
+            seen.push(current);
+            if let Some(task) = self.tasks.get(&current) {
+                stack.extend(task.dependencies().iter().copied());
+            }
+        }
+        false
+    }
+
+    /// Pending dependencies a task is still waiting for.
+    pub fn blocking_tasks(&self, task: &Task) -> Vec<&Task> {
+        task.dependencies().iter()
+            .filter_map(|id| self.tasks.get(id))
+            .filter(|dependency| dependency.is_pending())
             .collect()
     }
 
-    pub fn list_tasks(&self) -> Vec<&Task> {
-        self.tasks.values().collect()
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        task.is_pending() && (task.status() == Status::Blocked || !self.blocking_tasks(task).is_empty())
     }
-    
-    pub fn clear_tasks(&mut self) {
-        self.tasks.clear();
+
+    /// Pending tasks that can be worked on right now.
+    pub fn list_actionable_tasks(&self) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self.tasks.values()
+            .filter(|task| task.is_pending() && !task.is_archived() && !self.is_blocked(task))
+            .collect();
+        tasks.sort_by_key(|task| task.id());
+        tasks
     }
-    
-    pub fn pending_task_count(&self) -> usize {
-        self.tasks.values().filter(|task| !task.completed()).count()
+
+    pub fn list_blocked_tasks(&self) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self.tasks.values()
+            .filter(|task| self.is_blocked(task) && !task.is_archived())
+            .collect();
+        tasks.sort_by_key(|task| task.id());
+        tasks
     }
-    
-    pub fn list_completed_tasks(&self) -> Vec<&Task> {
-        self.tasks.values().filter(|task| task.completed()).collect()
+
+    /// Moves a task under another one, or to the top level with `None`.
+    pub fn set_parent(&mut self, id: u32, parent: Option<u32>) -> Result<(), String> {
+        if !self.tasks.contains_key(&id) {
+            return Err("Task not found.".to_string());
+        }
+        if let Some(parent) = parent {
+            if !self.tasks.contains_key(&parent) {
+                return Err("Parent task not found.".to_string());
+            }
+            if parent == id || self.descendants_of(id).contains(&parent) {
+                return Err("A task can't be a subtask of itself or of its own subtasks.".to_string());
+            }
+        }
+        self.record(format!("Move '{}'", self.tasks[&id].name()), |manager| {
+            let task = manager.tasks.get_mut(&id).unwrap();
+            task.set_parent(parent);
+            task.touch();
+            manager.mark_changed(id);
+        });
+        Ok(())
+    }
+
+    pub fn subtasks_of(&self, id: u32) -> Vec<&Task> {
+        let mut subtasks: Vec<&Task> = self.tasks.values()
+            .filter(|task| task.parent() == Some(id))
+            .collect();
+        subtasks.sort_by_key(|task| (task.deadline().is_none(), task.deadline(), task.id()));
+        subtasks
+    }
+
+    /// IDs of all subtasks at any depth below a task.
+    pub fn descendants_of(&self, id: u32) -> Vec<u32> {
+        let mut descendants = Vec::new();
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            for subtask in self.subtasks_of(current) {
+                // Guard against cycles in hand-edited files
+                if subtask.id() != id && !descendants.contains(&subtask.id()) {
+                    descendants.push(subtask.id());
+                    stack.push(subtask.id());
+                }
+            }
+        }
+        descendants
+    }
+
+    /// Completed and total number of subtasks at any depth, or `None` for tasks without subtasks.
+    pub fn subtask_progress(&self, id: u32) -> Option<(usize, usize)> {
+        let descendants = self.descendants_of(id);
+        if descendants.is_empty() {
+            return None;
+        }
+        let done = descendants.iter().filter(|id| !self.tasks[id].is_pending()).count();
+        Some((done, descendants.len()))
+    }
+
+    /// Whether a task is shown under a pending parent rather than on its own.
+    pub fn has_pending_parent(&self, task: &Task) -> bool {
+        task.parent()
+            .and_then(|parent| self.tasks.get(&parent))
+            .is_some_and(|parent| parent.is_pending())
+    }
+
+    /// Lists the given tasks, each followed by its pending subtasks depth-first,
+    /// paired with their depth in the tree.
+    pub fn with_pending_subtasks<'a>(&'a self, tasks: Vec<&'a Task>) -> Vec<(usize, &'a Task)> {
+        let mut tree = Vec::new();
+        let mut stack: Vec<(usize, &Task)> = tasks.into_iter().rev().map(|task| (0, task)).collect();
+        while let Some((depth, task)) = stack.pop() {
+            if tree.iter().any(|(_, listed): &(usize, &Task)| listed.id() == task.id()) {
+                continue;
+            }
+            tree.push((depth, task));
+            for subtask in self.subtasks_of(task.id()).into_iter().rev() {
+                if subtask.is_pending() && !subtask.is_archived() {
+                    stack.push((depth + 1, subtask));
+                }
+            }
+        }
+        tree
+    }
+
+    /// Names of all projects that have tasks, sorted.
+    pub fn projects(&self) -> Vec<&str> {
+        let projects: BTreeSet<&str> = self.tasks.values().filter_map(|task| task.project()).collect();
+        projects.into_iter().collect()
+    }
+
+    /// Every task in a project, archived or not.
+    pub fn tasks_in_project(&self, project: &str) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self.tasks.values()
+            .filter(|task| task.project() == Some(project))
+            .collect();
+        tasks.sort_by_key(|task| task.id());
+        tasks
+    }
+
+    pub fn project_pending_count(&self, project: &str) -> usize {
+        self.tasks_in_project(project).iter().filter(|task| task.is_pending()).count()
+    }
+
+    pub fn project_completed_count(&self, project: &str) -> usize {
+        self.tasks_in_project(project).iter().filter(|task| task.status() == Status::Done).count()
+    }
+
+    /// Whether a project has tasks and all of them are archived.
+    pub fn is_project_archived(&self, project: &str) -> bool {
+        let tasks = self.tasks_in_project(project);
+        !tasks.is_empty() && tasks.iter().all(|task| task.is_archived())
+    }
+
+    /// Archives every task in a project, or brings them back, as one change.
+    /// Returns how many tasks changed.
+    pub fn set_project_archived(&mut self, project: &str, archived: bool) -> Result<usize, String> {
+        let tasks = self.tasks_in_project(project);
+        if tasks.is_empty() {
+            return Err(format!("No project named '{}'.", project));
+        }
+        let ids: Vec<u32> = tasks.iter()
+            .filter(|task| task.is_archived() != archived)
+            .map(|task| task.id())
+            .collect();
+        let label = format!("{} project '{}'", if archived { "Archive" } else { "Unarchive" }, project);
+        self.record(label, |manager| {
+            for &id in &ids {
+                let task = manager.tasks.get_mut(&id).unwrap();
+                task.set_archived(archived);
+                task.touch();
+                manager.mark_changed(id);
+            }
+        });
+        Ok(ids.len())
+    }
+
+    pub fn changed_task_ids(&self) -> Vec<u32> {
+        self.changed.iter().copied().collect()
+    }
+
+    pub fn has_unsaved_changes(&self) -> bool {
+        !self.changed.is_empty()
+    }
+
+    /// Marks the given tasks as saved, telling the change callback only once.
+    pub fn mark_saved_many(&mut self, ids: &[u32]) {
+        for id in ids {
+            self.changed.remove(id);
+        }
+        self.notify();
+    }
+
+    pub fn mark_all_saved(&mut self) {
+        self.changed.clear();
+        self.notify();
     }
-    
-    pub fn list_pending_tasks(&self) -> Vec<&Task> {
-        self.tasks.values().filter(|task| !task.completed()).collect()
+
+    // Marks a task as unsaved and as part of the change being recorded
+    fn mark_changed(&mut self, id: u32) {
+        self.changed.insert(id);
+        if let Some(recording) = &mut self.recording {
+            recording.touched.insert(id);
+        }
     }
-    
-    pub fn mark_task_completed(&mut self, name: &str) -> Option<&Task> {
-        if let Some(task) = self.tasks.get_mut(name) {
-            task.mark_completed();
-            Some(task)
-        } else {
-            None
+
+    /// Runs a change and puts it on the undo stack if it touched any task.
+    /// Changes made from inside another one are part of it, so several
+    /// changes can be undone as one.
+    pub fn record<T>(&mut self, label: String, change: impl FnOnce(&mut Self) -> T) -> T {
+        if self.recording.is_some() {
+            return change(self);
+        }
+        self.recording = Some(Recording { snapshot: self.tasks.clone(), touched: BTreeSet::new() });
+        let result = change(self);
+        let Recording { snapshot, touched } = self.recording.take().unwrap();
+        for id in &touched {
+            self.log_changes(*id, snapshot.get(id).cloned().as_ref());
+        }
+        if !touched.is_empty() {
+            self.undo_stack.push(Revision {
+                label,
+                before: touched.iter().map(|id| (*id, snapshot.get(id).cloned())).collect(),
+                after: touched.iter().map(|id| (*id, self.tasks.get(id).cloned())).collect(),
+            });
+            if self.undo_stack.len() > UNDO_LIMIT {
+                self.undo_stack.remove(0);
+            }
+            self.redo_stack.clear();
+        }
+        self.notify();
+        result
+    }
+
+    // Puts tasks back the way a revision recorded them
+    fn restore(&mut self, tasks: &[(u32, Option<Task>)]) {
+        for (id, task) in tasks {
+            let before = self.tasks.get(id).cloned();
+            match task {
+                Some(task) => {
+                    self.tasks.insert(*id, task.clone());
+                }
+                None => {
+                    self.tasks.remove(id);
+                }
+            }
+            self.changed.insert(*id);
+            self.log_changes(*id, before.as_ref());
         }
+        self.notify();
     }
-}
\ No newline at end of file
+
+    // Adds history entries for how a task differs from an earlier version of it
+    fn log_changes(&mut self, id: u32, before: Option<&Task>) {
+        let timestamp = Utc::now();
+        for (field, old_value, new_value) in field_changes(before, self.tasks.get(&id)) {
+            self.history.push(HistoryEntry {
+                task_id: id,
+                field: field.to_string(),
+                old_value,
+                new_value,
+                timestamp,
+                user: self.user.clone(),
+            });
+        }
+    }
+
+    /// Adds history read from storage, which counts as saved.
+    pub fn load_history(&mut self, entries: Vec<HistoryEntry>) {
+        // IDs of deleted tasks still have a history, so they are never given out again
+        if let Some(next) = entries.iter().map(|entry| entry.task_id).max().and_then(|highest| highest.checked_add(1)) {
+            self.next_id = self.next_id.max(next);
+        }
+        let unsaved = self.history.len() - self.saved_history;
+        self.history.splice(0..0, entries);
+        self.saved_history = self.history.len() - unsaved;
+    }
+
+    /// Every change made to a task, oldest first.
+    pub fn history_of(&self, id: u32) -> Vec<&HistoryEntry> {
+        self.history.iter().filter(|entry| entry.task_id == id).collect()
+    }
+
+    /// History entries recorded since the history was last saved.
+    pub fn unsaved_history(&self) -> &[HistoryEntry] {
+        &self.history[self.saved_history..]
+    }
+
+    pub fn mark_history_saved(&mut self) {
+        self.saved_history = self.history.len();
+        self.notify();
+    }
+
+    /// Reverts the last change and returns its description.
+    pub fn undo(&mut self) -> Option<String> {
+        let revision = self.undo_stack.pop()?;
+        self.restore(&revision.before);
+        let label = revision.label.clone();
+        self.redo_stack.push(revision);
+        Some(label)
+    }
+
+    /// Applies the last undone change again and returns its description.
+    pub fn redo(&mut self) -> Option<String> {
+        let revision = self.redo_stack.pop()?;
+        self.restore(&revision.after);
+        let label = revision.label.clone();
+        self.undo_stack.push(revision);
+        Some(label)
+    }
+
+    /// Description of the change `undo` would revert.
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo_stack.last().map(|revision| revision.label.as_str())
+    }
+
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo_stack.last().map(|revision| revision.label.as_str())
+    }
+}
@@ -0,0 +1,520 @@
+use std::io;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use crate::console::{format_duration, local_time, status_marker};
+use crate::status::Status;
+use crate::storage::{persist_changes, Storage};
+use crate::task::Task;
+use crate::task_manager::TaskManager;
+
+/// Fields that can be edited inline, in the order they are listed.
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Description,
+    Due,
+    Tags,
+    Project,
+    Priority,
+    Repeat,
+    Status,
+}
+
+impl Field {
+    const ALL: [Field; 8] = [Field::Name, Field::Description, Field::Due, Field::Tags, Field::Project, Field::Priority, Field::Repeat, Field::Status];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Field::Name => "Name",
+            Field::Description => "Description",
+            Field::Due => "Due",
+            Field::Tags => "Tags",
+            Field::Project => "Project",
+            Field::Priority => "Priority",
+            Field::Repeat => "Repeat",
+            Field::Status => "Status",
+        }
+    }
+
+    fn value(&self, task: &Task) -> String {
+        match self {
+            Field::Name => task.name().to_string(),
+            Field::Description => task.description().unwrap_or_default().to_string(),
+            Field::Due => task.due_date_as_str().unwrap_or_default(),
+            Field::Tags => task.tags_csv(),
+            Field::Project => task.project().unwrap_or_default().to_string(),
+            Field::Priority => task.priority().to_string(),
+            Field::Repeat => task.recurrence_as_str().unwrap_or_default(),
+            Field::Status => task.status().to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Input {
+    Field(Field),
+    NewTask,
+    NewSubtask,
+}
+
+enum Mode {
+    Browse,
+    /// Picking a field of the selected task, by index into `Field::ALL`
+    Edit(usize),
+    Input { target: Input, buffer: String },
+    ConfirmDelete,
+}
+
+struct App {
+    /// Depth and ID of each line in the task list
+    rows: Vec<(usize, u32)>,
+    list: ListState,
+    show_closed: bool,
+    mode: Mode,
+    message: Option<(String, bool)>,
+    /// Task to select on the next refresh, e.g. one that was just added
+    jump_to: Option<u32>,
+    quit: bool,
+}
+
+/// Runs the full-screen interface until the user quits. Changes are saved
+/// after each key press if autosave is on; saving on exit is left to the caller.
+pub fn run(task_manager: &mut TaskManager, storage: &dyn Storage, autosave: bool) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, task_manager, storage, autosave);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    task_manager: &mut TaskManager,
+    storage: &dyn Storage,
+    autosave: bool,
+) -> io::Result<()> {
+    let mut app = App {
+        rows: Vec::new(),
+        list: ListState::default().with_selected(Some(0)),
+        show_closed: false,
+        mode: Mode::Browse,
+        message: None,
+        jump_to: None,
+        quit: false,
+    };
+    app.refresh(task_manager, None);
+
+    while !app.quit {
+        terminal.draw(|frame| draw(frame, &mut app, task_manager))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        app.handle_key(key, task_manager);
+        let selected = app.jump_to.take().or(app.selected_id());
+        app.refresh(task_manager, selected);
+
+        if (autosave || storage.updates_incrementally())
+            && let Err(e) = persist_changes(task_manager, storage) {
+            app.error(format!("Error saving tasks: {}", e));
+        }
+    }
+    Ok(())
+}
+
+impl App {
+    fn selected_id(&self) -> Option<u32> {
+        self.list.selected().and_then(|index| self.rows.get(index)).map(|(_, id)| *id)
+    }
+
+    fn info(&mut self, message: impl Into<String>) {
+        self.message = Some((message.into(), false));
+    }
+
+    fn error(&mut self, message: impl Into<String>) {
+        self.message = Some((message.into(), true));
+    }
+
+    /// Rebuilds the list the same way the menu lists tasks, keeping the
+    /// selection on the same task if it is still shown.
+    fn refresh(&mut self, task_manager: &TaskManager, selected: Option<u32>) {
+        let roots = |tasks: Vec<&Task>| -> Vec<(usize, u32)> {
+            let roots = tasks.into_iter().filter(|task| !task_manager.has_pending_parent(task)).collect();
+            task_manager.with_pending_subtasks(roots).into_iter().map(|(depth, task)| (depth, task.id())).collect()
+        };
+        self.rows = roots(task_manager.get_all_pending_tasks_with_due_date());
+        self.rows.extend(roots(task_manager.get_all_pending_tasks_without_due_date()));
+        if self.show_closed {
+            self.rows.extend(task_manager.get_all_tasks().into_iter()
+                .filter(|task| !task.is_pending() && !task.is_archived())
+                .map(|task| (0, task.id())));
+        }
+
+        let index = selected
+            .and_then(|id| self.rows.iter().position(|(_, row)| *row == id))
+            .or(self.list.selected())
+            .map(|index| index.min(self.rows.len().saturating_sub(1)));
+        self.list.select(index);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, task_manager: &mut TaskManager) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        match &mut self.mode {
+            Mode::Browse => self.browse(key, task_manager),
+            Mode::Edit(field) => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => *field = field.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => *field = (*field + 1).min(Field::ALL.len() - 1),
+                KeyCode::Enter => {
+                    let field = Field::ALL[*field];
+                    let value = self.selected_id()
+                        .and_then(|id| task_manager.get_task(id))
+                        .map(|task| field.value(task))
+                        .unwrap_or_default();
+                    self.mode = Mode::Input { target: Input::Field(field), buffer: value };
+                }
+                KeyCode::Esc | KeyCode::Char('q') => self.mode = Mode::Browse,
+                _ => {}
+            },
+            Mode::Input { target, buffer } => match key.code {
+                KeyCode::Enter => {
+                    let (target, value) = (*target, buffer.trim().to_string());
+                    self.submit(target, value, task_manager);
+                }
+                KeyCode::Esc => self.mode = mode_after(*target),
+                KeyCode::Backspace => {
+                    buffer.pop();
+                }
+                KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => buffer.clear(),
+                KeyCode::Char(c) => buffer.push(c),
+                _ => {}
+            },
+            Mode::ConfirmDelete => {
+                if key.code == KeyCode::Char('y')
+                    && let Some(id) = self.selected_id()
+                    && let Some(task) = task_manager.remove_task(id) {
+                    self.info(format!("Deleted '{}'.", task.name()));
+                } else {
+                    self.info("Task not deleted.");
+                }
+                self.mode = Mode::Browse;
+            }
+        }
+    }
+
+    fn browse(&mut self, key: KeyEvent, task_manager: &mut TaskManager) {
+        self.message = None;
+        let selected = self.selected_id();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Home | KeyCode::Char('g') => self.list.select_first(),
+            KeyCode::End | KeyCode::Char('G') => self.list.select(Some(self.rows.len().saturating_sub(1))),
+            KeyCode::PageUp => self.list.scroll_up_by(10),
+            KeyCode::PageDown => self.list.scroll_down_by(10),
+            KeyCode::Tab => {
+                self.show_closed = !self.show_closed;
+                self.info(if self.show_closed { "Showing closed tasks." } else { "Hiding closed tasks." });
+            }
+            KeyCode::Char('a') => self.mode = Mode::Input { target: Input::NewTask, buffer: String::new() },
+            KeyCode::Char('A') if selected.is_some() => {
+                self.mode = Mode::Input { target: Input::NewSubtask, buffer: String::new() };
+            }
+            KeyCode::Char('e') | KeyCode::Enter if selected.is_some() => self.mode = Mode::Edit(0),
+            KeyCode::Char('s') if selected.is_some() => {
+                let status = task_manager.get_task(selected.unwrap()).unwrap().status().to_string();
+                self.mode = Mode::Input { target: Input::Field(Field::Status), buffer: status };
+            }
+            KeyCode::Char('d') if selected.is_some() => self.mode = Mode::ConfirmDelete,
+            KeyCode::Char('c') if selected.is_some() => self.change_status(task_manager, selected.unwrap(), Status::Done),
+            KeyCode::Char('u') => match task_manager.undo() {
+                Some(label) => self.info(format!("Undone: {}", label)),
+                None => self.error("Nothing to undo."),
+            },
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => match task_manager.redo() {
+                Some(label) => self.info(format!("Redone: {}", label)),
+                None => self.error("Nothing to redo."),
+            },
+            KeyCode::Char('r') if selected.is_some() => self.change_status(task_manager, selected.unwrap(), Status::Todo),
+            _ => {}
+        }
+    }
+
+    fn change_status(&mut self, task_manager: &mut TaskManager, id: u32, status: Status) {
+        match task_manager.set_status(id, status) {
+            Ok(change) => {
+                let mut message = format!("Status set to {}.", status);
+                if let Some(next) = change.next_occurrence.and_then(|id| task_manager.get_task(id)) {
+                    message.push_str(&format!(" Next occurrence due {}.", next.due_as_local_str().unwrap_or_default()));
+                }
+                if !change.closed_subtasks.is_empty() {
+                    message.push_str(&format!(" Also closed {} subtask(s).", change.closed_subtasks.len()));
+                }
+                if !change.unblocked.is_empty() {
+                    message.push_str(&format!(" Unblocked {} task(s).", change.unblocked.len()));
+                }
+                self.info(message);
+            }
+            Err(e) => self.error(e),
+        }
+    }
+
+    fn submit(&mut self, target: Input, value: String, task_manager: &mut TaskManager) {
+        let selected = self.selected_id();
+        let result = match target {
+            Input::NewTask | Input::NewSubtask if value.is_empty() => Err("Task name is empty".to_string()),
+            Input::NewTask | Input::NewSubtask => {
+                let mut task = Task::new(value);
+                if target == Input::NewSubtask {
+                    task.set_parent(selected);
+                }
+                self.jump_to = Some(task_manager.add_task(task));
+                Ok("Task added.".to_string())
+            }
+            Input::Field(Field::Status) => match (value.parse::<Status>(), selected) {
+                (Ok(status), Some(id)) => {
+                    self.change_status(task_manager, id, status);
+                    self.mode = Mode::Browse;
+                    return;
+                }
+                (Err(e), _) => Err(e),
+                (_, None) => Err("Task not found.".to_string()),
+            },
+            Input::Field(field) => match selected.and_then(|id| task_manager.get_task(id)) {
+                Some(task) => {
+                    let mut task = task.clone();
+                    apply_field(&mut task, field, value).map(|_| {
+                        task_manager.update_task(task);
+                        format!("{} updated.", field.label())
+                    })
+                }
+                None => Err("Task not found.".to_string()),
+            },
+        };
+        match result {
+            Ok(message) => {
+                self.info(message);
+                self.mode = mode_after(target);
+            }
+            // Stay in the input so the value can be fixed
+            Err(e) => self.error(e),
+        }
+    }
+}
+
+/// Where to go once an input is saved or cancelled.
+fn mode_after(target: Input) -> Mode {
+    match target {
+        Input::Field(field) => Mode::Edit(Field::ALL.iter().position(|f| *f == field).unwrap()),
+        Input::NewTask | Input::NewSubtask => Mode::Browse,
+    }
+}
+
+fn apply_field(task: &mut Task, field: Field, value: String) -> Result<(), String> {
+    match field {
+        Field::Name if value.is_empty() => return Err("Task name is empty".to_string()),
+        Field::Name => task.set_name(value),
+        Field::Description if value.is_empty() => task.clear_description(),
+        Field::Description => task.set_description(value),
+        Field::Due if value.is_empty() => task.clear_due_date(),
+        Field::Due => task.set_due_date(value)?,
+        Field::Tags => task.set_tags(value.split(',')
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()),
+        Field::Project if value.trim().is_empty() => task.clear_project(),
+        Field::Project => task.set_project(value.trim().to_string()),
+        Field::Priority => task.set_priority(value.parse().map_err(|_| "Priority must be a number.".to_string())?)?,
+        Field::Repeat if value.is_empty() => task.clear_recurrence(),
+        Field::Repeat => task.set_recurrence(value)?,
+        Field::Status => task.set_status(value.parse()?),
+    }
+    Ok(())
+}
+
+fn draw(frame: &mut Frame, app: &mut App, task_manager: &TaskManager) {
+    let [main, status_line, help_line] = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ]).areas(frame.area());
+    let [list_area, detail_area] = Layout::horizontal([
+        Constraint::Percentage(55),
+        Constraint::Percentage(45),
+    ]).areas(main);
+
+    let items: Vec<ListItem> = app.rows.iter()
+        .filter_map(|(depth, id)| task_manager.get_task(*id).map(|task| (*depth, task)))
+        .map(|(depth, task)| ListItem::new(task_line(task_manager, depth, task)))
+        .collect();
+    let title = format!(" Tasks ({} pending) ", task_manager.pending_task_count());
+    let list = List::new(items)
+        .block(Block::bordered().title(title))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, list_area, &mut app.list);
+
+    let task = app.selected_id().and_then(|id| task_manager.get_task(id));
+    let details = match (&app.mode, task) {
+        (Mode::Edit(selected), Some(task)) => edit_lines(task, *selected),
+        (Mode::Input { target: Input::Field(field), .. }, Some(task)) => {
+            let selected = Field::ALL.iter().position(|f| f == field).unwrap();
+            edit_lines(task, selected)
+        }
+        (_, Some(task)) => detail_lines(task_manager, task),
+        (_, None) => vec![Line::from("No task selected.".dark_gray())],
+    };
+    frame.render_widget(
+        Paragraph::new(details).wrap(Wrap { trim: false }).block(Block::bordered().title(" Details ")),
+        detail_area,
+    );
+
+    let status = match &app.mode {
+        Mode::Input { target, buffer } => {
+            let label = match target {
+                Input::Field(field) => field.label(),
+                Input::NewTask => "New task",
+                Input::NewSubtask => "New subtask",
+            };
+            let mut spans = vec![format!("{}: ", label).bold(), Span::raw(buffer.clone()), "█".into()];
+            if *target == Input::Field(Field::Due) && !buffer.trim().is_empty() {
+                spans.push(due_preview(buffer));
+            }
+            Line::from(spans)
+        }
+        Mode::ConfirmDelete => {
+            let subtasks = app.selected_id().map(|id| task_manager.descendants_of(id).len()).unwrap_or(0);
+            let question = if subtasks > 0 {
+                format!("Delete this task and {} subtask(s)? (y/n)", subtasks)
+            } else {
+                "Delete this task? (y/n)".to_string()
+            };
+            Line::from(question.yellow())
+        }
+        _ => match &app.message {
+            Some((message, true)) => Line::from(message.clone().red()),
+            Some((message, false)) => Line::from(message.clone().green()),
+            None => Line::default(),
+        },
+    };
+    frame.render_widget(Paragraph::new(status), status_line);
+
+    let help = match app.mode {
+        Mode::Browse => "↑↓/jk move  a add  A subtask  e edit  c complete  r reopen  s status  d delete  u undo  ^R redo  Tab closed  q quit",
+        Mode::Edit(_) => "↑↓/jk choose field  Enter edit  Esc back",
+        Mode::Input { .. } => "Enter save  Esc cancel  Ctrl+U clear",
+        Mode::ConfirmDelete => "y delete  any other key cancels",
+    };
+    frame.render_widget(Paragraph::new(help.dark_gray()), help_line);
+}
+
+/// Shows what a due date being typed resolves to.
+fn due_preview(input: &str) -> Span<'static> {
+    let mut preview = Task::new(String::new());
+    match preview.set_due_date(input.to_string()) {
+        Ok(_) => {
+            let weekday = preview.due_date().unwrap().format("%A");
+            format!("  → {} ({})", preview.due_as_local_str().unwrap(), weekday).cyan()
+        }
+        Err(_) => "  → not a date yet".dark_gray(),
+    }
+}
+
+fn task_line<'a>(task_manager: &TaskManager, depth: usize, task: &'a Task) -> Line<'a> {
+    let mut spans = Vec::new();
+    if depth > 0 {
+        spans.push(Span::raw(format!("{}└ ", "  ".repeat(depth - 1))));
+    }
+    spans.push(Span::raw(format!("{} ", status_marker(task.status()))));
+    spans.push(Span::styled(format!("{:>2} ", task.priority()), Style::new().fg(Color::Yellow)));
+    if let Some(due) = task.due_as_local_str() {
+        let style = if task.is_overdue() { Style::new().fg(Color::Red) } else { Style::new() };
+        spans.push(Span::styled(format!("{} ", due), style));
+    }
+    let name_style = if !task.is_pending() {
+        Style::new().add_modifier(Modifier::CROSSED_OUT | Modifier::DIM)
+    } else if task_manager.is_blocked(task) {
+        Style::new().add_modifier(Modifier::DIM)
+    } else {
+        Style::new()
+    };
+    spans.push(Span::styled(task.name().as_str(), name_style));
+    if task.recurrence().is_some() {
+        spans.push(" ↻".cyan());
+    }
+    if let Some((done, total)) = task_manager.subtask_progress(task.id()) {
+        spans.push(Span::raw(format!(" ({}/{})", done, total)));
+    }
+    Line::from(spans)
+}
+
+fn detail_lines<'a>(task_manager: &'a TaskManager, task: &'a Task) -> Vec<Line<'a>> {
+    let field = |label: &'a str, value: String| Line::from(vec![format!("{}: ", label).bold(), Span::raw(value)]);
+    let mut lines = vec![
+        Line::from(task.name().as_str().bold()),
+        Line::default(),
+        field("ID", task.id().to_string()),
+        field("Status", task.status().label().to_string()),
+        field("Priority", task.priority().to_string()),
+    ];
+    if let Some(parent) = task.parent().and_then(|parent| task_manager.get_task(parent)) {
+        lines.push(field("Subtask of", format!("{} (ID {})", parent.name(), parent.id())));
+    }
+    if let Some(due) = task.due_as_local_str() {
+        lines.push(field("Due", due));
+    }
+    if !task.tags().is_empty() {
+        lines.push(field("Tags", task.tags_csv()));
+    }
+    if let Some(project) = task.project() {
+        let archived = if task.is_archived() { " (archived)" } else { "" };
+        lines.push(field("Project", format!("{}{}", project, archived)));
+    }
+    if let Some(recurrence) = task.recurrence_as_str() {
+        lines.push(field("Repeats", recurrence));
+    }
+    if !task.dependencies().is_empty() {
+        let dependencies: Vec<String> = task.dependencies().iter()
+            .filter_map(|id| task_manager.get_task(*id))
+            .map(|dependency| format!("{} ({})", dependency.name(), dependency.status()))
+            .collect();
+        lines.push(field("Depends on", dependencies.join(", ")));
+    }
+    if task_manager.is_blocked(task) {
+        lines.push(Line::from("Blocked".yellow()));
+    }
+    lines.push(field("Created", local_time(task.created_at())));
+    lines.push(field("Last changed", local_time(task.updated_at())));
+    if let (Some(completed_at), Some(cycle_time)) = (task.completed_at(), task.cycle_time()) {
+        lines.push(field("Completed", format!("{} (took {})", local_time(completed_at), format_duration(cycle_time))));
+    }
+    if let Some(description) = task.description() {
+        lines.push(Line::default());
+        lines.push(Line::from(description));
+    }
+    if let Some((done, total)) = task_manager.subtask_progress(task.id()) {
+        lines.push(Line::default());
+        lines.push(field("Subtasks", format!("{}/{} done", done, total)));
+        for subtask in task_manager.subtasks_of(task.id()) {
+            lines.push(Line::from(format!("  {} {}", status_marker(subtask.status()), subtask.name())));
+        }
+    }
+    lines
+}
+
+fn edit_lines(task: &Task, selected: usize) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(format!("Editing: {}", task.name()).bold()), Line::default()];
+    for (index, field) in Field::ALL.iter().enumerate() {
+        let line = Line::from(format!("{:<12} {}", field.label(), field.value(task)));
+        lines.push(if index == selected { line.reversed() } else { line });
+    }
+    lines
+}
@@ -0,0 +1,402 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use colored::Colorize;
+use crate::console::{clear_console, local_time, read_input, task_actions, wait};
+use crate::query::Query;
+use crate::storage::write_atomically;
+use crate::task::Task;
+use crate::task_manager::TaskManager;
+
+pub const VIEWS_FILE: &str = "views.conf";
+
+/// Longest text shown in a name or description cell
+const MAX_TEXT_WIDTH: usize = 40;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ViewColumn {
+    Id,
+    Priority,
+    Due,
+    Name,
+    Status,
+    Tags,
+    Project,
+    Description,
+    Parent,
+    Created,
+    Updated,
+    Completed,
+}
+
+const COLUMNS: [(&str, ViewColumn); 12] = [
+    ("id", ViewColumn::Id),
+    ("priority", ViewColumn::Priority),
+    ("due", ViewColumn::Due),
+    ("name", ViewColumn::Name),
+    ("status", ViewColumn::Status),
+    ("tags", ViewColumn::Tags),
+    ("project", ViewColumn::Project),
+    ("description", ViewColumn::Description),
+    ("parent", ViewColumn::Parent),
+    ("created", ViewColumn::Created),
+    ("updated", ViewColumn::Updated),
+    ("completed", ViewColumn::Completed),
+];
+
+pub const DEFAULT_COLUMNS: [ViewColumn; 4] = [ViewColumn::Priority, ViewColumn::Due, ViewColumn::Name, ViewColumn::Status];
+
+impl ViewColumn {
+    pub fn from_name(name: &str) -> Result<ViewColumn, String> {
+        let name = name.trim().to_lowercase();
+        COLUMNS.iter()
+            .find(|(column_name, _)| *column_name == name)
+            .map(|(_, column)| *column)
+            .ok_or_else(|| format!(
+                "Unknown column '{}'. Use one of: {}.",
+                name,
+                COLUMNS.iter().map(|(column_name, _)| *column_name).collect::<Vec<&str>>().join(", ")
+            ))
+    }
+
+    pub fn name(&self) -> &'static str {
+        COLUMNS.iter().find(|(_, column)| column == self).unwrap().0
+    }
+
+    fn value(&self, task: &Task) -> String {
+        match self {
+            ViewColumn::Id => task.id().to_string(),
+            ViewColumn::Priority => task.priority().to_string(),
+            ViewColumn::Due => task.due_as_local_str().unwrap_or_default(),
+            ViewColumn::Name => shorten(task.name()),
+            ViewColumn::Status => task.status().label().to_string(),
+            ViewColumn::Tags => task.tags_csv(),
+            ViewColumn::Project => task.project().unwrap_or_default().to_string(),
+            ViewColumn::Description => shorten(task.description().unwrap_or_default()),
+            ViewColumn::Parent => task.parent().map(|parent| parent.to_string()).unwrap_or_default(),
+            ViewColumn::Created => local_time(task.created_at()),
+            ViewColumn::Updated => local_time(task.updated_at()),
+            ViewColumn::Completed => task.completed_at().map(local_time).unwrap_or_default(),
+        }
+    }
+}
+
+fn shorten(text: &str) -> String {
+    if text.chars().count() > MAX_TEXT_WIDTH {
+        format!("{}…", text.chars().take(MAX_TEXT_WIDTH - 1).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+/// A named filter, sort order and set of columns, saved as a section of
+/// `views.conf` next to the data file:
+///
+/// ```text
+/// [urgent work]
+/// filter = tag:work and priority>=7 and not status:done
+/// sort = due,-priority
+/// columns = id, priority, due, name
+/// ```
+pub struct View {
+    pub name: String,
+    /// Query expression without a `sort:` clause
+    pub filter: String,
+    /// Comma-separated sort keys as in a query's `sort:` clause
+    pub sort: String,
+    pub columns: Vec<ViewColumn>,
+}
+
+impl View {
+    pub fn new(name: String) -> View {
+        View {
+            name,
+            filter: String::new(),
+            sort: String::new(),
+            columns: DEFAULT_COLUMNS.to_vec(),
+        }
+    }
+
+    /// A view from a full query, splitting off its `sort:` clause.
+    pub fn from_query(name: String, query: &str) -> Result<View, String> {
+        if !is_valid_name(&name) {
+            return Err(format!("Invalid view name '{}'. Names can't be empty or contain '[', ']' or line breaks.", name.trim()));
+        }
+        if query.contains(['\n', '\r']) {
+            return Err("A view's query must be on one line.".to_string());
+        }
+        let (filter, sort) = Query::split_sort(query).map_err(|e| e.to_string())?;
+        let mut view = View::new(name.trim().to_string());
+        view.filter = filter;
+        view.sort = sort;
+        // What is saved has to load again
+        view.query()?;
+        Ok(view)
+    }
+
+    pub fn query(&self) -> Result<Query, String> {
+        let query = if self.sort.is_empty() {
+            self.filter.clone()
+        } else {
+            format!("{} sort:{}", self.filter, self.sort)
+        };
+        Query::parse(&query).map_err(|e| format!("View '{}': {}", self.name, e))
+    }
+
+    pub fn tasks<'a>(&self, task_manager: &'a TaskManager) -> Result<Vec<&'a Task>, String> {
+        Ok(self.query()?.run(task_manager))
+    }
+}
+
+/// Whether a name can be written as a `[name]` line of the views file.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.trim().is_empty() && !name.contains(['[', ']', '\n', '\r'])
+}
+
+/// Where the views for a data file are kept.
+pub fn views_file(data_file: &str) -> PathBuf {
+    Path::new(data_file).with_file_name(VIEWS_FILE)
+}
+
+/// Reads the saved views. A missing file means there are none.
+pub fn load_views(path: &Path) -> Result<Vec<View>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let mut views: Vec<View> = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let error = |message: String| format!("{}:{}: {}", path.display(), number + 1, message);
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            if views.iter().any(|view| view.name.eq_ignore_ascii_case(name.trim())) {
+                return Err(error(format!("view '{}' is defined twice", name.trim())));
+            }
+            views.push(View::new(name.trim().to_string()));
+            continue;
+        }
+        let Some(view) = views.last_mut() else {
+            return Err(error("expected a [view name] line first".to_string()));
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(error("expected 'key = value'".to_string()));
+        };
+        let value = value.trim();
+        match key.trim() {
+            "filter" => {
+                Query::parse(value).map_err(|e| error(e.to_string()))?;
+                view.filter = value.to_string();
+            }
+            "sort" => {
+                Query::parse(&format!("sort:{}", value)).map_err(|e| error(e.message))?;
+                view.sort = value.to_string();
+            }
+            "columns" => {
+                view.columns = value.split(',')
+                    .filter(|column| !column.trim().is_empty())
+                    .map(ViewColumn::from_name)
+                    .collect::<Result<_, _>>()
+                    .map_err(error)?;
+            }
+            other => return Err(error(format!("unknown setting '{}'", other))),
+        }
+    }
+    Ok(views)
+}
+
+pub fn save_views(path: &Path, views: &[View]) -> io::Result<()> {
+    write_atomically(path, |file| {
+        for (i, view) in views.iter().enumerate() {
+            if i > 0 {
+                writeln!(file)?;
+            }
+            writeln!(file, "[{}]", view.name)?;
+            writeln!(file, "filter = {}", view.filter)?;
+            if !view.sort.is_empty() {
+                writeln!(file, "sort = {}", view.sort)?;
+            }
+            let columns: Vec<&str> = view.columns.iter().map(|column| column.name()).collect();
+            writeln!(file, "columns = {}", columns.join(", "))?;
+        }
+        Ok(())
+    })
+}
+
+/// Adds a view, replacing any saved one with the same name.
+pub fn add_view(path: &Path, view: View) -> Result<(), String> {
+    let mut views = load_views(path)?;
+    views.retain(|saved| !saved.name.eq_ignore_ascii_case(&view.name));
+    views.push(view);
+    save_views(path, &views).map_err(|e| format!("Cannot save {}: {}", path.display(), e))
+}
+
+pub fn find_view<'a>(views: &'a [View], name: &str) -> Option<&'a View> {
+    views.iter().find(|view| view.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Prints tasks as a table with the view's columns, numbering the rows when
+/// `numbered` is set.
+pub fn print_view(view: &View, tasks: &[&Task], numbered: bool) {
+    let rows: Vec<Vec<String>> = tasks.iter()
+        .map(|task| view.columns.iter().map(|column| column.value(task)).collect())
+        .collect();
+    let widths: Vec<usize> = view.columns.iter().enumerate()
+        .map(|(i, column)| rows.iter().map(|row| row[i].chars().count()).chain([column.name().len()]).max().unwrap())
+        .collect();
+    let number_width = if numbered { tasks.len().to_string().len() + 2 } else { 0 };
+
+    let header: Vec<String> = view.columns.iter().zip(&widths)
+        .map(|(column, width)| format!("{:<width$}", column.name().to_uppercase(), width = width))
+        .collect();
+    println!("{}{}", " ".repeat(number_width), header.join("  ").bold());
+    for (i, (task, row)) in tasks.iter().zip(&rows).enumerate() {
+        let cells: Vec<String> = row.iter().zip(&widths).zip(&view.columns)
+            .map(|((cell, width), column)| {
+                let cell = format!("{:<width$}", cell, width = width);
+                match column {
+                    ViewColumn::Due if task.is_overdue() => cell.red().to_string(),
+                    ViewColumn::Name if !task.is_pending() => cell.dimmed().to_string(),
+                    _ => cell,
+                }
+            })
+            .collect();
+        if numbered {
+            print!("{:<width$}", format!("{}.", i + 1), width = number_width);
+        }
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+pub fn display_views(task_manager: &mut TaskManager, data_file: &str) {
+    let path = views_file(data_file);
+    loop {
+        clear_console();
+        let views = match load_views(&path) {
+            Ok(views) => views,
+            Err(e) => {
+                println!("Error: {}", e);
+                wait();
+                return;
+            }
+        };
+        if views.is_empty() {
+            println!("No saved views.");
+        } else {
+            println!("Saved views:");
+        }
+        for (i, view) in views.iter().enumerate() {
+            let sort = if view.sort.is_empty() { String::new() } else { format!(" sort:{}", view.sort) };
+            println!("{}. {} {}", i + 1, view.name.bold(), format!("{}{}", view.filter, sort).dimmed());
+        }
+
+        let choice = read_input("\nEnter a view number to open it, 'N' to create one, 'D <number>' to delete one, or nothing to go back");
+        let upper = choice.to_uppercase();
+        if choice.is_empty() {
+            return;
+        } else if upper == "N" {
+            create_view(&path);
+        } else if let Some(number) = upper.strip_prefix("D ") {
+            match number.trim().parse::<usize>() {
+                Ok(number) if number > 0 && number <= views.len() => {
+                    let mut views = views;
+                    let removed = views.remove(number - 1);
+                    match save_views(&path, &views) {
+                        Ok(()) => println!("View '{}' deleted.", removed.name),
+                        Err(e) => println!("Error saving views: {}", e),
+                    }
+                }
+                _ => println!("Invalid view number."),
+            }
+            wait();
+        } else {
+            match choice.parse::<usize>() {
+                Ok(number) if number > 0 && number <= views.len() => display_view(task_manager, &views[number - 1]),
+                _ => {
+                    println!("Invalid option.");
+                    wait();
+                }
+            }
+        }
+    }
+}
+
+/// Shows the tasks in a view as the task list until the user goes back.
+pub fn display_view(task_manager: &mut TaskManager, view: &View) {
+    loop {
+        clear_console();
+        let tasks = match view.tasks(task_manager) {
+            Ok(tasks) => tasks,
+            Err(e) => {
+                println!("Error: {}", e);
+                wait();
+                return;
+            }
+        };
+        println!("{}\n", view.name.bold());
+        if tasks.is_empty() {
+            println!("No tasks in this view.");
+        }
+        print_view(view, &tasks, true);
+        let ids: Vec<u32> = tasks.iter().map(|task| task.id()).collect();
+
+        let choice = read_input("\nEnter task number to manage it, or nothing to go back");
+        match choice.parse::<usize>() {
+            Ok(index) if index > 0 && index <= ids.len() => task_actions(task_manager, ids[index - 1]),
+            _ => return,
+        }
+    }
+}
+
+fn create_view(path: &Path) {
+    clear_console();
+    let name = read_input("Enter view name:");
+    if !is_valid_name(&name) {
+        println!("Invalid view name.");
+        wait();
+        return;
+    }
+    let mut view = View::new(name);
+    loop {
+        view.filter = read_input("Enter filter (a query such as 'tag:work and not status:done', empty for all tasks):");
+        match Query::parse(&view.filter) {
+            Ok(_) => break,
+            Err(e) => println!("{}", e.explain(&view.filter)),
+        }
+    }
+    loop {
+        view.sort = read_input("Enter sort order (e.g. 'due,-priority', empty to sort by ID):").replace(' ', "");
+        match Query::parse(&format!("sort:{}", view.sort)) {
+            Ok(_) => break,
+            Err(_) if view.sort.is_empty() => break,
+            Err(e) => println!("{}", e.message),
+        }
+    }
+    loop {
+        let input = read_input(&format!(
+            "Enter columns (comma-separated from {}; empty for {}):",
+            COLUMNS.iter().map(|(name, _)| *name).collect::<Vec<&str>>().join(", "),
+            DEFAULT_COLUMNS.iter().map(|column| column.name()).collect::<Vec<&str>>().join(", ")
+        ));
+        if input.is_empty() {
+            break;
+        }
+        match input.split(',').filter(|column| !column.trim().is_empty()).map(ViewColumn::from_name).collect() {
+            Ok(columns) => {
+                view.columns = columns;
+                break;
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    let name = view.name.clone();
+    match add_view(path, view) {
+        Ok(()) => println!("View '{}' saved.", name),
+        Err(e) => println!("Error: {}", e),
+    }
+    wait();
+}